@@ -0,0 +1,59 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use firetail::ingesters::{
+    file_log::{FileLogIngester, FileSource},
+    parallel::ParallelFileIngester,
+    LogIngester,
+};
+use tokio::runtime::Runtime;
+
+const LINES: u64 = 100_000;
+
+const SYSLOG_LINE: &str = "<134>1 2025-01-02T12:00:00+01:00 fw1.local filterlog 12345 - \
+    [meta sequenceId=\"1\"] 96,,,fae559338f65e11c53669fc3642c93c2,vlan0.20,match,pass,out,\
+    4,0x0,,127,61633,0,DF,6,tcp,52,192.168.10.15,192.168.20.14,\
+    52461,9100,0,S,3442468761,,64240,,mss;nop;wscale;nop;nop;sackOK";
+
+//Compares reading a whole file line by line with the parallel block parser.
+fn ingest(c: &mut Criterion) {
+    let path = std::env::temp_dir().join(format!("firetail-bench-{}.log", std::process::id()));
+    let content = format!("{}\n", SYSLOG_LINE).repeat(LINES as usize);
+    std::fs::write(&path, &content).unwrap();
+
+    let runtime = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("ingest");
+    group.throughput(Throughput::Elements(LINES));
+    group.sample_size(10);
+
+    group.bench_function("file_log", |b| {
+        b.to_async(&runtime).iter(|| async {
+            let source = FileSource::new(&path).await.unwrap();
+            let mut ingester = FileLogIngester::new(source);
+            ingester.ingest().await.unwrap();
+            assert_eq!(ingester.get_logs().read().unwrap().len(), LINES as usize);
+        })
+    });
+
+    let max_workers = std::thread::available_parallelism().map_or(1, |n| n.get());
+    for workers in [1, 2, 4, 8]
+        .into_iter()
+        .filter(|&n| n <= max_workers.max(2))
+    {
+        group.bench_with_input(
+            BenchmarkId::new("parallel", workers),
+            &workers,
+            |b, &workers| {
+                b.to_async(&runtime).iter(|| async {
+                    let mut ingester = ParallelFileIngester::new(&path).unwrap().workers(workers);
+                    ingester.ingest().await.unwrap();
+                    assert_eq!(ingester.get_logs().read().unwrap().len(), LINES as usize);
+                })
+            },
+        );
+    }
+
+    group.finish();
+    std::fs::remove_file(path).unwrap();
+}
+
+criterion_group!(benches, ingest);
+criterion_main!(benches);
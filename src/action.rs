@@ -4,7 +4,11 @@ pub enum Action {
     Quit,
     LogViewAction(LogViewAction),
     ToggleInfoPopup,
+    ToggleRejectsPopup,
+    ToggleReplayPause,
+    ReplayStep,
     DateSearchBegin,
+    FilterBegin,
     Edit(KeyEvent),
     EditDone,
     EditAbort,
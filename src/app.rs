@@ -1,6 +1,12 @@
 use crate::{
     action::{self, Action},
-    ui::{log_info::LogInfoPopup, log_view::LogView},
+    filter::{expr::Expr, Filter},
+    ingesters::{command::CommandStatus, rejects::RejectedLogs, replay::ReplayControl},
+    packet_filter::FilterControl,
+    ui::{
+        command_status::CommandStatusBar, log_info::LogInfoPopup, log_view::LogView,
+        rejects::RejectsPopup,
+    },
 };
 use action::LogViewAction::*;
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
@@ -15,6 +21,7 @@ use ratatui::{
 
 use std::{
     io,
+    str::FromStr,
     sync::{Arc, RwLock},
     time::Duration,
 };
@@ -25,16 +32,43 @@ use tokio::{
 };
 use tui_input::{backend::crossterm::EventHandler, Input};
 
-use crate::TimestampedLog;
+use crate::{
+    store::{LogStore, Ring},
+    timestamp::DisplayZone,
+};
 pub struct App {
     pub exit: bool,
     pub show_log_info_popup: bool,
+    pub show_rejects_popup: bool,
     pub date_input: Input,
+    pub filter_input: Input,
+    prompt: Prompt,
     action_rx: UnboundedReceiver<Action>,
+    command_status: Option<Arc<RwLock<CommandStatus>>>,
+    replay: Option<Arc<ReplayControl>>,
+    rejects: Arc<RwLock<RejectedLogs>>,
+    zone: DisplayZone,
+    //Filter set on the command line, its expression is replaced by the filter prompt.
+    filter: Filter,
+    filter_control: Option<Arc<FilterControl>>,
+    //Expression of the filter in use, restored when editing is aborted.
+    filter_expr: String,
+    filter_error: Option<String>,
+    filter_generation: usize,
+    //Index in the store of the selected log, to select it again once the logs are filtered again.
+    selected_index: Option<usize>,
     //Ui elements
     pub log_view: LogView,
 }
 
+//Prompt edited in InputMode::Editing.
+#[derive(Default, PartialEq, Eq)]
+pub enum Prompt {
+    #[default]
+    Date,
+    Filter,
+}
+
 #[derive(Default)]
 pub enum InputMode {
     #[default]
@@ -44,17 +78,31 @@ pub enum InputMode {
 
 impl App {
     pub fn new(
-        logs: Arc<RwLock<Vec<TimestampedLog>>>,
-        filtered_logs: Arc<RwLock<Vec<usize>>>,
+        logs: Arc<RwLock<LogStore>>,
+        filtered_logs: Arc<RwLock<Ring<usize>>>,
+        rejects: Arc<RwLock<RejectedLogs>>,
     ) -> Self {
         let (action_tx, action_rx) = unbounded_channel::<Action>();
 
         let app = Self {
             exit: false,
             date_input: Input::default(),
+            filter_input: Input::default(),
+            prompt: Prompt::default(),
             show_log_info_popup: false,
+            show_rejects_popup: false,
             log_view: LogView::new(logs, filtered_logs),
             action_rx,
+            command_status: None,
+            replay: None,
+            rejects,
+            zone: DisplayZone::default(),
+            filter: Filter::default(),
+            filter_control: None,
+            filter_expr: String::new(),
+            filter_error: None,
+            filter_generation: 0,
+            selected_index: None,
         };
 
         let tick_tx = action_tx.clone();
@@ -74,6 +122,39 @@ impl App {
         app
     }
 
+    //Show the status of the command logs are read from.
+    pub fn command_status(mut self, command_status: Arc<RwLock<CommandStatus>>) -> Self {
+        self.command_status = Some(command_status);
+        self
+    }
+
+    //Let the keyboard pause and step a replay.
+    pub fn replay(mut self, replay: Arc<ReplayControl>) -> Self {
+        self.replay = Some(replay);
+        self
+    }
+
+    //Timezone logs are shown in and dates are searched in.
+    pub fn zone(mut self, zone: DisplayZone) -> Self {
+        self.zone = zone;
+        self.log_view.log_table.zone = zone;
+        self
+    }
+
+    //Let the filter prompt replace the expression of `filter`, starting from `expr`.
+    pub fn filter(
+        mut self,
+        filter: Filter,
+        expr: Option<String>,
+        control: Arc<FilterControl>,
+    ) -> Self {
+        self.filter = filter;
+        self.filter_expr = expr.unwrap_or_default();
+        self.filter_input = Input::new(self.filter_expr.clone());
+        self.filter_control = Some(control);
+        self
+    }
+
     pub async fn update(&mut self, action: Action) {
         match action {
             Action::Quit => self.exit = true,
@@ -86,21 +167,51 @@ impl App {
                 self.show_log_info_popup = !self.show_log_info_popup;
             }
 
+            Action::ToggleRejectsPopup => {
+                self.show_rejects_popup = !self.show_rejects_popup;
+            }
+
+            Action::ToggleReplayPause => {
+                if let Some(replay) = &self.replay {
+                    replay.toggle_pause();
+                }
+            }
+
+            Action::ReplayStep => {
+                if let Some(replay) = &self.replay {
+                    replay.step();
+                }
+            }
+
             Action::DateSearchBegin => {
+                self.prompt = Prompt::Date;
                 self.date_input.reset();
             }
 
-            Action::EditAbort => {
-                self.date_input.reset();
+            Action::FilterBegin => {
+                self.prompt = Prompt::Filter;
             }
 
+            Action::EditAbort => match self.prompt {
+                Prompt::Date => self.date_input.reset(),
+                Prompt::Filter => {
+                    self.filter_input = Input::new(self.filter_expr.clone());
+                    self.filter_error = None;
+                }
+            },
+
+            Action::EditDone if self.prompt == Prompt::Filter => self.apply_filter(),
+
             Action::EditDone => {
                 let selected_log = self.log_view.get_selected_log();
                 if let Some(selected_log) = selected_log {
-                    if let Ok(datetime) = App::parse_date_time(
-                        selected_log.timestamp.date_naive(),
+                    let datetime = App::parse_date_time(
+                        self.zone.date_naive(&selected_log.timestamp),
                         self.date_input.value().to_owned(),
-                    ) {
+                    )
+                    .ok()
+                    .and_then(|datetime| self.zone.from_naive(&datetime));
+                    if let Some(datetime) = datetime {
                         self.log_view.select_closest_date(datetime);
                     } else {
                         self.date_input.reset();
@@ -109,33 +220,109 @@ impl App {
             }
 
             Action::Edit(key_event) => {
-                self.date_input.handle_event(&Event::Key(key_event));
+                let input = match self.prompt {
+                    Prompt::Date => &mut self.date_input,
+                    Prompt::Filter => &mut self.filter_input,
+                };
+                input.handle_event(&Event::Key(key_event));
             }
 
             Action::Tick => {}
         }
     }
 
+    //Installs the filter typed in the prompt, an empty prompt only keeps the command line options.
+    //On error the prompt keeps the expression so that it can be fixed.
+    fn apply_filter(&mut self) {
+        let Some(control) = &self.filter_control else {
+            return;
+        };
+        let value = self.filter_input.value().trim();
+        let expr = match value {
+            "" => None,
+            value => match Expr::from_str(value) {
+                Ok(expr) => Some(expr),
+                Err(e) => {
+                    self.filter_error =
+                        Some(format!("{} at column {}", e.message, e.column(value)));
+                    return;
+                }
+            },
+        };
+
+        let mut filter = self.filter.clone();
+        filter.set_expr(expr);
+        control.set_filter(filter);
+        self.filter_expr = value.to_owned();
+        self.filter_error = None;
+    }
+
+    //Once the logs were filtered again, selects the log closest to the one selected before.
+    fn sync_filter(&mut self) {
+        let Some(control) = &self.filter_control else {
+            return;
+        };
+        let filtered_logs = self.log_view.filtered_logs.clone();
+        let filtered_logs = filtered_logs.read().unwrap();
+        //Read while the ring is locked, it is replaced along with the generation.
+        let generation = control.status().generation;
+        if generation == self.filter_generation {
+            self.selected_index = filtered_logs
+                .get(self.log_view.log_table.selected_packet)
+                .copied();
+            return;
+        }
+        drop(filtered_logs);
+
+        self.filter_generation = generation;
+        self.log_view
+            .select_nearest_log(self.selected_index.unwrap_or_default());
+    }
+
     pub async fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
         while !self.exit {
+            self.sync_filter();
             terminal.draw(|frame| {
+                let command_status_height = match self.command_status {
+                    Some(_) => 4,
+                    None => 0,
+                };
                 let layout = Layout::default()
                     .direction(Direction::Vertical)
-                    .constraints([Constraint::Min(1), Constraint::Length(3)])
+                    .constraints([
+                        Constraint::Min(1),
+                        Constraint::Length(command_status_height),
+                        Constraint::Length(3),
+                    ])
                     .split(frame.area());
 
                 self.log_view.draw(frame, layout[0]);
 
                 if self.show_log_info_popup {
                     if let Some(log) = self.log_view.get_selected_log() {
-                        let popup = LogInfoPopup::new(log);
+                        let popup = LogInfoPopup::new(log, self.zone);
                         frame.render_widget(popup, frame.area());
                     }
                 }
 
-                let footer =
-                    Layout::horizontal([Constraint::Percentage(20), Constraint::Percentage(80)])
-                        .split(layout[1]);
+                if self.show_rejects_popup {
+                    if let Ok(rejects) = self.rejects.read() {
+                        frame.render_widget(RejectsPopup::new(&rejects), frame.area());
+                    }
+                }
+
+                if let Some(command_status) = &self.command_status {
+                    if let Ok(command_status) = command_status.read() {
+                        frame.render_widget(CommandStatusBar::new(&command_status), layout[1]);
+                    }
+                }
+
+                let footer = Layout::horizontal([
+                    Constraint::Percentage(15),
+                    Constraint::Percentage(35),
+                    Constraint::Percentage(50),
+                ])
+                .split(layout[2]);
 
                 let date_search =
                     Paragraph::new(Text::from(format!("Date: {}", self.date_input.value())))
@@ -144,14 +331,46 @@ impl App {
 
                 frame.render_widget(date_search, footer[0]);
 
-                // Footer with centered instructions
-                let instructions =
-                    Paragraph::new(Text::from(" i: Show log info |  q: Quit "))
-                        .centered()
-                        .style(Style::default().fg(Color::Gray))
-                        .block(Block::bordered());
+                let filter_status = self.filter_control.as_ref().map(|c| c.status());
+                let filter_block = match (&self.filter_error, filter_status) {
+                    (Some(error), _) => Block::bordered().red().title(error.as_str()),
+                    (None, Some(status)) if status.rebuilding => {
+                        Block::bordered().yellow().title("filtering...")
+                    }
+                    _ => Block::bordered().yellow(),
+                };
+                let filter_prompt =
+                    Paragraph::new(Text::from(format!("Filter: {}", self.filter_input.value())))
+                        .block(filter_block);
+
+                frame.render_widget(filter_prompt, footer[1]);
 
-                frame.render_widget(instructions, footer[1]);
+                // Footer with centered instructions
+                let rejected = self.rejects.read().map(|r| r.total()).unwrap_or_default();
+                let replay = match &self.replay {
+                    Some(replay) => {
+                        let status = replay.status();
+                        let state = match (status.done, status.paused) {
+                            (true, _) => "done",
+                            (false, true) => "paused",
+                            (false, false) => "playing",
+                        };
+                        format!(
+                            " Replay {}x {} ({}) |  space: Pause/Resume |  n: Step |",
+                            status.speed, state, status.replayed
+                        )
+                    }
+                    None => String::new(),
+                };
+                let instructions = Paragraph::new(Text::from(format!(
+                    "{} f: Filter |  i: Show log info |  r: Rejected lines ({}) |  q: Quit ",
+                    replay, rejected
+                )))
+                .centered()
+                .style(Style::default().fg(Color::Gray))
+                .block(Block::bordered());
+
+                frame.render_widget(instructions, footer[2]);
             })?;
 
             let action = self.action_rx.recv().await;
@@ -163,11 +382,16 @@ impl App {
         Ok(())
     }
 
+    //Reading terminal events blocks, so it doesn't run on the async workers.
+    //Events are polled with a timeout to notice when the app is gone.
     pub fn run_event_listener(action_tx: UnboundedSender<Action>) {
-        tokio::spawn(async move {
+        tokio::task::spawn_blocking(move || {
             let mut input_mode = InputMode::default();
 
-            loop {
+            while !action_tx.is_closed() {
+                if !event::poll(Duration::from_millis(200)).unwrap_or(false) {
+                    continue;
+                }
                 let maybe_event = event::read();
 
                 if let Ok(event) = maybe_event {
@@ -193,10 +417,21 @@ impl App {
                                 KeyCode::Char('i') => {
                                     action_tx.send(Action::ToggleInfoPopup).unwrap()
                                 }
+                                KeyCode::Char('r') => {
+                                    action_tx.send(Action::ToggleRejectsPopup).unwrap()
+                                }
+                                KeyCode::Char(' ') => {
+                                    action_tx.send(Action::ToggleReplayPause).unwrap()
+                                }
+                                KeyCode::Char('n') => action_tx.send(Action::ReplayStep).unwrap(),
                                 KeyCode::Char('d') => {
                                     action_tx.send(Action::DateSearchBegin).unwrap();
                                     input_mode = InputMode::Editing;
                                 }
+                                KeyCode::Char('f') => {
+                                    action_tx.send(Action::FilterBegin).unwrap();
+                                    input_mode = InputMode::Editing;
+                                }
                                 _ => {}
                             },
 
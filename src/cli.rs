@@ -1,17 +1,111 @@
-use std::str::FromStr;
+use std::{collections::HashSet, fmt, io, path::PathBuf, str::FromStr, time::Duration};
 
+use chrono::Local;
 use clap::Parser;
-use senpa::{Action, ProtoName};
+use senpa::{Action, Dir, ProtoName};
 
-use crate::{cidr::IpCidr, filter::Filter, port_filter::Ports};
+use crate::{
+    cidr::IpCidr,
+    filter::{
+        expr::{parse_ip_version, Expr, ExprError},
+        Filter,
+    },
+    ingesters::{dialect::Dialect, tls::TlsOptions},
+    port_filter::Ports,
+    tcp_flags::TcpFlags,
+    timestamp::{parse_duration, parse_time_bound, DisplayZone, TimeWindow},
+};
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 pub struct Cli {
-    pub logfile: Option<String>,
+    /// Log files to read, glob patterns are expanded. Several files are merged by timestamp,
+    /// the logs of each file must be in chronological order
+    pub logfiles: Vec<String>,
+    /// Read every log file in a directory, glob patterns are expanded
+    #[clap(long)]
+    pub dir: Vec<String>,
+    /// Keep reading the log file as it grows, following rotations
+    #[clap(short, long, requires = "logfiles", conflicts_with = "dir")]
+    pub follow: bool,
+    /// Memory map the log file and parse the logs only when shown or filtered,
+    /// for multi-gigabyte files. The file must not be truncated while open,
+    /// e.g. by a copytruncate rotation, firetail would crash
+    #[clap(long, requires = "logfiles", conflicts_with_all = ["follow", "dir", "max_logs", "max_memory"])]
+    pub lazy: bool,
+    /// Save the line index built by --lazy beside the log file and reuse it
+    #[clap(long = "index-cache", requires = "lazy")]
+    pub index_cache: bool,
+    /// Replay the log file with the time elapsed between its logs, divided by SPEED
+    /// (--replay=10 is ten times faster). Space pauses and resumes, n steps
+    #[clap(
+        long,
+        value_name = "SPEED",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "1",
+        value_parser = parse_speed,
+        requires = "logfiles",
+        conflicts_with_all = ["follow", "dir", "lazy"]
+    )]
+    pub replay: Option<f64>,
+    /// Receive logs from the network instead of a file, e.g. udp://0.0.0.0:5514,
+    /// tcp://0.0.0.0:1514 or tls://0.0.0.0:6514
+    #[clap(long, conflicts_with_all = ["logfiles", "dir"])]
+    pub listen: Option<String>,
+    /// Read logs from the output of a shell command, e.g. "ssh fw1 opnsense-log -f filter"
+    #[clap(long, conflicts_with_all = ["logfiles", "dir", "listen"])]
+    pub exec: Option<String>,
+    /// Restart the --exec command with backoff when it exits
+    #[clap(long, requires = "exec")]
+    pub restart: bool,
+    /// PEM certificate chain of the TLS listener
+    #[clap(long = "tls-cert", requires = "listen")]
+    pub tls_cert: Option<PathBuf>,
+    /// PEM private key of the TLS listener
+    #[clap(long = "tls-key", requires = "listen")]
+    pub tls_key: Option<PathBuf>,
+    /// PEM CA used to verify client certificates, enables mutual authentication
+    #[clap(long = "tls-client-ca", requires = "listen")]
+    pub tls_client_ca: Option<PathBuf>,
+    /// Write every line that fails to parse to this file, with the reason
+    #[clap(long = "dump-rejects")]
+    pub dump_rejects: Option<PathBuf>,
+    /// Filterlog format of the firewall that wrote the logs
+    #[clap(long, value_enum, default_value_t = Dialect::Auto)]
+    pub format: Dialect,
+    /// Write every raw line read, including the ones that fail to parse, to this file
+    #[clap(long, conflicts_with = "lazy")]
+    pub tee: Option<PathBuf>,
+    /// Rotate the --tee file once it would grow over this size, e.g. 100M
+    #[clap(long = "tee-max-size", requires = "tee", value_parser = parse_size)]
+    pub tee_max_size: Option<usize>,
+    /// Rotate the --tee file once it is older than this, e.g. 1h or 1d
+    #[clap(long = "tee-max-age", requires = "tee", value_parser = parse_age)]
+    pub tee_max_age: Option<Duration>,
+    /// Drop logs older than this, e.g. "2025-01-02 10:00", 2025-01-02T10:00:00Z or -2h
+    #[clap(long, allow_hyphen_values = true)]
+    pub since: Option<String>,
+    /// Drop logs newer than this, same formats as --since. Reading a file stops
+    /// once past it, files are expected to be sorted
+    #[clap(long, allow_hyphen_values = true)]
+    pub until: Option<String>,
+    /// Timezone logs are shown in and typed dates are read in: local, UTC or an
+    /// IANA name like Europe/Rome
+    #[clap(long, default_value_t = DisplayZone::Local)]
+    pub tz: DisplayZone,
+    /// Keep at most this many logs in memory, the oldest ones are evicted
+    #[clap(long = "max-logs")]
+    pub max_logs: Option<usize>,
+    /// Keep at most about this much memory of logs, e.g. 512M or 2G
+    #[clap(long = "max-memory", value_parser = parse_size)]
+    pub max_memory: Option<usize>,
     #[clap(short, value_delimiter = ',')]
     pub interfaces: Vec<String>,
     #[clap(short, value_delimiter = ',')]
     pub protocols: Vec<String>,
+    /// Hostnames of the firewalls the logs are sent by
+    #[clap(long, value_delimiter = ',')]
+    pub host: Vec<String>,
     #[clap(short, value_delimiter = ',')]
     pub actions: Vec<String>,
     #[clap(long = "src-ip", value_delimiter = ',')]
@@ -22,6 +116,58 @@ pub struct Cli {
     pub src_port: Vec<String>,
     #[clap(long = "dst-port", value_delimiter = ',')]
     pub dst_port: Vec<String>,
+    /// Direction of the packets: in or out
+    #[clap(long, value_delimiter = ',')]
+    pub direction: Vec<String>,
+    /// Ip version of the packets: 4 or 6
+    #[clap(long = "ip-version", value_delimiter = ',', value_parser = parse_ip_version)]
+    pub ip_version: Vec<u8>,
+    /// Ip lengths or ranges of lengths, e.g. 0-60
+    #[clap(long, value_delimiter = ',')]
+    pub length: Vec<String>,
+    /// TTLs (hop limits for ipv6) or ranges of TTLs, e.g. 1-5
+    #[clap(long, value_delimiter = ',')]
+    pub ttl: Vec<String>,
+    /// Tcp flags checked like pf does, FLAGS/MASK: S/SA matches SYN without ACK.
+    /// Letters: F S R P A U E W
+    #[clap(long = "tcp-flags", value_delimiter = ',')]
+    pub tcp_flags: Vec<String>,
+    /// Numbers of the rules that logged the packets
+    #[clap(long, value_delimiter = ',')]
+    pub rule: Vec<u32>,
+    /// Trackers of the rules that logged the packets, the rule ids on OPNsense
+    #[clap(long, value_delimiter = ',')]
+    pub tracker: Vec<String>,
+    /// Drop the logs of these interfaces, even when listed in -i
+    #[clap(long = "not-interface", value_delimiter = ',')]
+    pub not_interfaces: Vec<String>,
+    /// Drop the logs of these protocols, even when listed in -p
+    #[clap(long = "not-proto", value_delimiter = ',')]
+    pub not_protocols: Vec<String>,
+    /// Drop the logs sent by these hostnames, even when listed in --host
+    #[clap(long = "not-host", value_delimiter = ',')]
+    pub not_host: Vec<String>,
+    /// Drop the logs with these actions, even when listed in -a
+    #[clap(long = "not-action", value_delimiter = ',')]
+    pub not_actions: Vec<String>,
+    /// Drop the logs from these addresses or networks, even when matched by --src-ip
+    #[clap(long = "not-src-ip", value_delimiter = ',')]
+    pub not_src_ip: Vec<String>,
+    /// Drop the logs to these addresses or networks, even when matched by --dst-ip
+    #[clap(long = "not-dst-ip", value_delimiter = ',')]
+    pub not_dst_ip: Vec<String>,
+    /// Drop the logs from these ports or ranges, even when matched by --src-port
+    #[clap(long = "not-src-port", value_delimiter = ',')]
+    pub not_src_port: Vec<String>,
+    /// Drop the logs to these ports or ranges, even when matched by --dst-port
+    #[clap(long = "not-dst-port", value_delimiter = ',')]
+    pub not_dst_port: Vec<String>,
+    /// Filter expression combined with the other filters, e.g.
+    /// "action=block and dir=in and dst in 10.0.0.0/8 and not dport 53 or iface vlan0.30".
+    /// Fields: action, dir, proto, iface, host, src, dst, ip, sport, dport, port,
+    /// ipver, len, ttl, flags, rule, tracker
+    #[clap(long = "where", value_name = "EXPR")]
+    pub where_expr: Option<String>,
 }
 
 #[allow(clippy::enum_variant_names)]
@@ -33,9 +179,132 @@ pub enum FilterError {
     InvalidDstIp(String),
     InvalidSrcPort(String),
     InvalidDstPort(String),
+    InvalidDir(String),
+    InvalidLength(String),
+    InvalidTtl(String),
+    InvalidTcpFlags(String),
+    InvalidWhere(String, ExprError),
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterError::InvalidProto(proto) => write!(f, "invalid protocol: {}", proto),
+            FilterError::InvalidAction(action) => write!(f, "invalid action: {}", action),
+            FilterError::InvalidSrcIp(ip) => write!(f, "invalid source ip: {}", ip),
+            FilterError::InvalidDstIp(ip) => write!(f, "invalid destination ip: {}", ip),
+            FilterError::InvalidSrcPort(port) => write!(f, "invalid source port: {}", port),
+            FilterError::InvalidDstPort(port) => write!(f, "invalid destination port: {}", port),
+            FilterError::InvalidDir(dir) => write!(f, "invalid direction: {}", dir),
+            FilterError::InvalidLength(length) => write!(f, "invalid length: {}", length),
+            FilterError::InvalidTtl(ttl) => write!(f, "invalid ttl: {}", ttl),
+            FilterError::InvalidTcpFlags(flags) => write!(f, "invalid tcp flags: {}", flags),
+            FilterError::InvalidWhere(expr, e) => write!(f, "--where: {}", e.show(expr)),
+        }
+    }
+}
+
+//Parses a size in bytes with an optional K, M or G suffix (powers of 1024).
+fn parse_size(s: &str) -> Result<usize, String> {
+    let s = s.trim();
+    let (number, unit) = match s.char_indices().find(|(_, c)| !c.is_ascii_digit()) {
+        Some((index, _)) => s.split_at(index),
+        None => (s, ""),
+    };
+
+    let multiplier: usize = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        _ => return Err(format!("invalid size unit: {}", unit)),
+    };
+
+    number
+        .parse::<usize>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .ok_or_else(|| format!("invalid size: {}", s))
+}
+
+fn parse_age(s: &str) -> Result<Duration, String> {
+    parse_duration(s)?
+        .to_std()
+        .map_err(|_| format!("invalid duration: {}", s))
+}
+
+fn parse_speed(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(speed) if speed.is_finite() && speed > 0.0 => Ok(speed),
+        _ => Err(format!("invalid speed: {}", s)),
+    }
+}
+
+fn expand_glob(pattern: &str) -> io::Result<Vec<PathBuf>> {
+    let paths = glob::glob(pattern)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("{}: {}", pattern, e)))?;
+
+    Ok(paths.filter_map(Result::ok).collect())
 }
 
 impl Cli {
+    pub fn log_files(&self) -> io::Result<Vec<PathBuf>> {
+        let mut log_files = Vec::new();
+
+        for logfile in &self.logfiles {
+            match expand_glob(logfile)?.as_slice() {
+                //Keep the path as is, opening it reports what is wrong.
+                [] => log_files.push(PathBuf::from(logfile)),
+                paths => log_files.extend_from_slice(paths),
+            }
+        }
+
+        for dir in &self.dir {
+            let pattern = PathBuf::from(dir).join("*");
+            let files: Vec<_> = expand_glob(&pattern.to_string_lossy())?
+                .into_iter()
+                .filter(|path| path.is_file())
+                .collect();
+
+            if files.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("no log files found in {}", dir),
+                ));
+            }
+            log_files.extend(files);
+        }
+
+        //A file given twice, e.g. by name and by a glob, is read once.
+        let mut seen = HashSet::new();
+        log_files.retain(|path| seen.insert(path.canonicalize().unwrap_or_else(|_| path.clone())));
+
+        Ok(log_files)
+    }
+
+    //Dates without an offset are read in the --tz zone.
+    pub fn time_window(&self) -> Result<TimeWindow, String> {
+        let now = Local::now();
+        let parse = |bound: &Option<String>| {
+            bound
+                .as_deref()
+                .map(|value| parse_time_bound(value, now, &self.tz))
+                .transpose()
+        };
+        Ok(TimeWindow {
+            since: parse(&self.since)?,
+            until: parse(&self.until)?,
+        })
+    }
+
+    pub fn tls_options(&self) -> Option<TlsOptions> {
+        Some(TlsOptions {
+            cert: self.tls_cert.clone()?,
+            key: self.tls_key.clone()?,
+            client_ca: self.tls_client_ca.clone(),
+        })
+    }
+
     pub fn build_filter(&self) -> Result<Filter, FilterError> {
         let mut filter = Filter::new();
 
@@ -43,6 +312,10 @@ impl Cli {
             .iter()
             .for_each(|interface| filter.add_interface(interface.to_lowercase().clone()));
 
+        self.host
+            .iter()
+            .for_each(|host| filter.add_host(host.clone()));
+
         for proto_str in &self.protocols {
             let proto = ProtoName::from_str(proto_str)
                 .map_err(|_| FilterError::InvalidProto(proto_str.into()))?;
@@ -79,6 +352,295 @@ impl Cli {
             filter.add_dst_port(port);
         }
 
+        for dir_str in &self.direction {
+            let dir = Dir::from_str(&dir_str.to_lowercase())
+                .map_err(|_| FilterError::InvalidDir(dir_str.into()))?;
+            filter.add_dir(dir);
+        }
+
+        self.ip_version
+            .iter()
+            .for_each(|&version| filter.add_ip_version(version));
+
+        for length_str in &self.length {
+            let length = Ports::from_str(length_str)
+                .map_err(|_| FilterError::InvalidLength(length_str.into()))?;
+            filter.add_length(length);
+        }
+
+        for ttl_str in &self.ttl {
+            let ttl =
+                Ports::from_str(ttl_str).map_err(|_| FilterError::InvalidTtl(ttl_str.into()))?;
+            filter.add_ttl(ttl);
+        }
+
+        for flags_str in &self.tcp_flags {
+            let flags = TcpFlags::from_str(flags_str)
+                .map_err(|_| FilterError::InvalidTcpFlags(flags_str.into()))?;
+            filter.add_tcp_flags(flags);
+        }
+
+        self.rule.iter().for_each(|&rule| filter.add_rule(rule));
+
+        self.tracker
+            .iter()
+            .for_each(|tracker| filter.add_tracker(tracker.clone()));
+
+        self.not_interfaces
+            .iter()
+            .for_each(|interface| filter.exclude_interface(interface.to_lowercase()));
+
+        self.not_host
+            .iter()
+            .for_each(|host| filter.exclude_host(host.clone()));
+
+        for proto_str in &self.not_protocols {
+            let proto = ProtoName::from_str(proto_str)
+                .map_err(|_| FilterError::InvalidProto(proto_str.into()))?;
+            filter.exclude_proto(proto);
+        }
+
+        for action_str in &self.not_actions {
+            let action = Action::from_str(action_str)
+                .map_err(|_| FilterError::InvalidAction(action_str.into()))?;
+            filter.exclude_action(action);
+        }
+
+        for ip_str in &self.not_src_ip {
+            let ip =
+                IpCidr::from_str(ip_str).map_err(|_| FilterError::InvalidSrcIp(ip_str.into()))?;
+            filter.exclude_src_ip(ip);
+        }
+
+        for ip_str in &self.not_dst_ip {
+            let ip =
+                IpCidr::from_str(ip_str).map_err(|_| FilterError::InvalidDstIp(ip_str.into()))?;
+            filter.exclude_dst_ip(ip);
+        }
+
+        for port_str in &self.not_src_port {
+            let port = Ports::from_str(port_str)
+                .map_err(|_| FilterError::InvalidSrcPort(port_str.into()))?;
+            filter.exclude_src_port(port);
+        }
+
+        for port_str in &self.not_dst_port {
+            let port = Ports::from_str(port_str)
+                .map_err(|_| FilterError::InvalidDstPort(port_str.into()))?;
+            filter.exclude_dst_port(port);
+        }
+
+        if let Some(expr) = &self.where_expr {
+            let parsed =
+                Expr::from_str(expr).map_err(|e| FilterError::InvalidWhere(expr.clone(), e))?;
+            filter.set_expr(Some(parsed));
+        }
+
         Ok(filter)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ingesters::{
+            parser::{FilterlogParser, LogParser},
+            tests::SYSLOG_LINE,
+        },
+        TimestampedLog,
+    };
+
+    fn log() -> TimestampedLog {
+        FilterlogParser::default().parse(SYSLOG_LINE).unwrap()
+    }
+
+    //Whether the filter built from the command line `args` keeps `log`.
+    fn keeps(log: &TimestampedLog, args: &[&str]) -> bool {
+        let cli = Cli::try_parse_from([&["firetail"], args].concat()).unwrap();
+        cli.build_filter().unwrap().test(log)
+    }
+
+    #[test]
+    fn test_duplicate_log_files() {
+        let cli = Cli::parse_from(["firetail", "Cargo.toml", "./Cargo.toml", "Cargo.t*"]);
+        assert_eq!(cli.log_files().unwrap(), [PathBuf::from("Cargo.toml")]);
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1000"), Ok(1000));
+        assert_eq!(parse_size("512K"), Ok(512 * 1024));
+        assert_eq!(parse_size("2G"), Ok(2 << 30));
+        assert_eq!(parse_size("64 MiB"), Ok(64 << 20));
+        assert!(parse_size("12T").is_err());
+        assert!(parse_size("M").is_err());
+    }
+
+    #[test]
+    fn test_host_filter() {
+        let log = log();
+        let (_, payload) = SYSLOG_LINE.split_once("] ").unwrap();
+        let bare = FilterlogParser::default().parse(payload).unwrap();
+
+        let cli = Cli::try_parse_from(["firetail", "--host", "fw2.local,FW1.local"]).unwrap();
+        let filter = cli.build_filter().unwrap();
+        assert!(filter.test(&log));
+        //Bare filterlog lines have no hostname.
+        assert!(!filter.test(&bare));
+
+        let cli = Cli::try_parse_from(["firetail", "--host", "fw2.local"]).unwrap();
+        assert!(!cli.build_filter().unwrap().test(&log));
+    }
+
+    #[test]
+    fn test_exclusions() {
+        let log = log();
+        let test = |args: &[&str]| keeps(&log, args);
+
+        assert!(test(&["--not-interface", "wan"]));
+        assert!(!test(&["--not-interface", "wan,VLAN0.20"]));
+        //Exclusions win over the inclusion lists.
+        assert!(!test(&["-i", "vlan0.20", "--not-interface", "vlan0.20"]));
+        assert!(test(&["--not-src-ip", "192.168.20.0/24"]));
+        assert!(!test(&["--not-src-ip", "192.168.10.0/24"]));
+        assert!(!test(&[
+            "--src-ip",
+            "192.168.0.0/16",
+            "--not-src-ip",
+            "192.168.10.0/28"
+        ]));
+        assert!(!test(&["--not-dst-ip", "192.168.20.14"]));
+        assert!(test(&["--not-dst-port", "53,123"]));
+        assert!(!test(&["--not-dst-port", "53,9000-9200"]));
+        assert!(!test(&["--not-src-port", "49152-65535"]));
+        assert!(!test(&["--not-proto", "udp,tcp"]));
+        assert!(!test(&["--not-action", "pass"]));
+        assert!(test(&["--not-host", "fw2.local"]));
+        assert!(!test(&["--not-host", "FW1.local"]));
+
+        let cli = Cli::try_parse_from(["firetail", "--not-dst-port", "http"]).unwrap();
+        assert_eq!(
+            cli.build_filter().unwrap_err(),
+            FilterError::InvalidDstPort("http".into())
+        );
+    }
+
+    #[test]
+    fn test_scan_filters() {
+        //Outbound ipv4 SYN of length 52 with TTL 127.
+        let log = log();
+        let test = |args: &[&str]| keeps(&log, args);
+
+        assert!(test(&["--direction", "out"]));
+        assert!(!test(&["--direction", "IN"]));
+        assert!(test(&["--ip-version", "4"]));
+        assert!(!test(&["--ip-version", "ipv6"]));
+        assert!(test(&["--length", "0-60,1400-1500"]));
+        assert!(!test(&["--length", "60-1500"]));
+        //Nested ranges.
+        assert!(test(&["--length", "0-100,40-50"]));
+        assert!(test(&["--ttl", "100-128"]));
+        assert!(!test(&["--ttl", "1-5"]));
+        assert!(test(&["--tcp-flags", "S/SA"]));
+        assert!(!test(&["--tcp-flags", "SA,/S"]));
+
+        assert!(Cli::try_parse_from(["firetail", "--ip-version", "5"]).is_err());
+        let cli = Cli::try_parse_from(["firetail", "--tcp-flags", "SYN"]).unwrap();
+        assert_eq!(
+            cli.build_filter().unwrap_err(),
+            FilterError::InvalidTcpFlags("SYN".into())
+        );
+        let cli = Cli::try_parse_from(["firetail", "--ttl", "128-64"]).unwrap();
+        assert_eq!(
+            cli.build_filter().unwrap_err(),
+            FilterError::InvalidTtl("128-64".into())
+        );
+    }
+
+    #[test]
+    fn test_rule_filter() {
+        let log = log();
+        let test = |args: &[&str]| keeps(&log, args);
+
+        assert!(test(&["--rule", "12,96"]));
+        assert!(!test(&["--rule", "12"]));
+        assert!(test(&["--tracker", "FAE559338F65E11C53669FC3642C93C2"]));
+        assert!(!test(&["--rule", "96", "--tracker", "1000000103"]));
+        assert!(Cli::try_parse_from(["firetail", "--rule", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_where_error() {
+        let cli = Cli::try_parse_from(["firetail", "--where", "dport 53 or"]).unwrap();
+        let Err(e) = cli.build_filter() else {
+            panic!("the expression is incomplete");
+        };
+        assert_eq!(
+            e.to_string(),
+            "--where: expected a field or `(` at column 12\n  dport 53 or\n             ^"
+        );
+    }
+
+    #[test]
+    fn test_tee_rotation() {
+        let cli = Cli::try_parse_from([
+            "firetail",
+            "--tee",
+            "live.log",
+            "--tee-max-size",
+            "100M",
+            "--tee-max-age",
+            "1d",
+        ])
+        .unwrap();
+        assert_eq!(cli.tee_max_size, Some(100 << 20));
+        assert_eq!(cli.tee_max_age, Some(Duration::from_secs(86400)));
+
+        assert!(Cli::try_parse_from(["firetail", "--tee-max-age", "1h"]).is_err());
+        assert!(
+            Cli::try_parse_from(["firetail", "--tee", "live.log", "--tee-max-age", "-1h"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_replay_speed() {
+        let cli = Cli::try_parse_from(["firetail", "--replay", "filter.log"]).unwrap();
+        assert_eq!(cli.replay, Some(1.0));
+        assert_eq!(cli.logfiles, ["filter.log"]);
+
+        let cli = Cli::try_parse_from(["firetail", "--replay=2.5", "filter.log"]).unwrap();
+        assert_eq!(cli.replay, Some(2.5));
+
+        assert!(Cli::try_parse_from(["firetail", "--replay=0", "filter.log"]).is_err());
+        assert!(Cli::try_parse_from(["firetail", "--replay"]).is_err());
+    }
+
+    #[test]
+    fn test_relative_time_window() {
+        let cli = Cli::try_parse_from(["firetail", "--since", "-2h", "filter.log"]).unwrap();
+        let since = cli.time_window().unwrap().since.unwrap();
+        assert!(
+            (Local::now() - since - chrono::Duration::hours(2))
+                .num_seconds()
+                .abs()
+                < 5
+        );
+        assert_eq!(cli.logfiles, ["filter.log"]);
+
+        let cli = Cli::try_parse_from(["firetail", "--until", "tomorrow"]).unwrap();
+        assert!(cli.time_window().is_err());
+
+        let cli = Cli::try_parse_from(["firetail", "--tz", "UTC", "--since", "2025-01-02 10:00"])
+            .unwrap();
+        assert_eq!(
+            cli.time_window().unwrap().since,
+            Some(
+                chrono::DateTime::parse_from_rfc3339("2025-01-02T10:00:00Z")
+                    .unwrap()
+                    .with_timezone(&Local)
+            )
+        );
+        assert!(Cli::try_parse_from(["firetail", "--tz", "Mars/Olympus"]).is_err());
+    }
+}
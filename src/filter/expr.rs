@@ -0,0 +1,518 @@
+use std::{fmt, iter::Peekable, str::FromStr, vec::IntoIter};
+
+use senpa::{Action, Dir, ProtoInfo, ProtoName};
+
+use super::{ip_version, tcp_flags_of, ttl};
+use crate::{
+    cidr::{CidrIpFilter, IpCidr},
+    port_filter::{PortFilter, Ports},
+    tcp_flags::TcpFlags,
+    TimestampedLog,
+};
+
+//Boolean filter expression, e.g.
+//`action=block and dir=in and dst in 10.0.0.0/8 and not dport 53 or iface vlan0.30`.
+//`not` binds tighter than `and`, which binds tighter than `or`.
+#[derive(Debug, Clone)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Predicate(Predicate),
+}
+
+//Matches a field of the log against a list of values, any value matching is enough.
+#[derive(Debug, Clone)]
+pub enum Predicate {
+    Action(Vec<Action>),
+    Dir(Vec<Dir>),
+    Proto(Vec<ProtoName>),
+    Interface(Vec<String>),
+    Host(Vec<String>),
+    Src(CidrIpFilter),
+    Dst(CidrIpFilter),
+    //Either the source or the destination.
+    Ip(CidrIpFilter),
+    SrcPort(PortFilter),
+    DstPort(PortFilter),
+    Port(PortFilter),
+    IpVersion(Vec<u8>),
+    Length(PortFilter),
+    Ttl(PortFilter),
+    TcpFlags(Vec<TcpFlags>),
+    Rule(Vec<u32>),
+    Tracker(Vec<String>),
+}
+
+//Why an expression could not be parsed, `position` is the byte offset of the
+//offending token.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ExprError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl ExprError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        Self {
+            position,
+            message: message.into(),
+        }
+    }
+
+    //Column of the error in `expr`, starting at 1.
+    pub fn column(&self, expr: &str) -> usize {
+        expr[..self.position.min(expr.len())].chars().count() + 1
+    }
+
+    //The expression with a caret under the error.
+    pub fn show(&self, expr: &str) -> String {
+        let column = self.column(expr);
+        format!(
+            "{} at column {}\n  {}\n  {}^",
+            self.message,
+            column,
+            expr,
+            " ".repeat(column - 1)
+        )
+    }
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for ExprError {}
+
+impl FromStr for Expr {
+    type Err = ExprError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?.into_iter().peekable(),
+            end: s.len(),
+        };
+        let expr = parser.or()?;
+        match parser.tokens.next() {
+            None => Ok(expr),
+            Some(token) => Err(ExprError::new(
+                token.position,
+                format!("expected `and` or `or` before {}", token.kind),
+            )),
+        }
+    }
+}
+
+impl Expr {
+    pub fn test(&self, log: &TimestampedLog) -> bool {
+        match self {
+            Expr::And(left, right) => left.test(log) && right.test(log),
+            Expr::Or(left, right) => left.test(log) || right.test(log),
+            Expr::Not(expr) => !expr.test(log),
+            Expr::Predicate(predicate) => predicate.test(log),
+        }
+    }
+}
+
+impl Predicate {
+    fn test(&self, timestamped_log: &TimestampedLog) -> bool {
+        let log = &timestamped_log.log;
+        match self {
+            Predicate::Action(actions) => actions.contains(&log.packet_filter.action),
+            Predicate::Dir(dirs) => dirs.contains(&log.packet_filter.dir),
+            Predicate::Proto(protocols) => protocols.contains(&log.protocol.name),
+            Predicate::Interface(interfaces) => interfaces.contains(&log.packet_filter.interface),
+            Predicate::Host(hosts) => {
+                timestamped_log
+                    .header
+                    .hostname
+                    .as_ref()
+                    .is_some_and(|hostname| {
+                        hosts.iter().any(|host| host.eq_ignore_ascii_case(hostname))
+                    })
+            }
+            Predicate::Src(ips) => ips.lookup(&log.ip_data.src),
+            Predicate::Dst(ips) => ips.lookup(&log.ip_data.dst),
+            Predicate::Ip(ips) => ips.lookup(&log.ip_data.src) || ips.lookup(&log.ip_data.dst),
+            Predicate::SrcPort(ports) => ports_of(log).is_some_and(|(src, _)| ports.contains(src)),
+            Predicate::DstPort(ports) => ports_of(log).is_some_and(|(_, dst)| ports.contains(dst)),
+            Predicate::Port(ports) => {
+                ports_of(log).is_some_and(|(src, dst)| ports.contains(src) || ports.contains(dst))
+            }
+            Predicate::IpVersion(versions) => versions.contains(&ip_version(log)),
+            Predicate::Length(lengths) => lengths.contains(log.ip_data.length),
+            Predicate::Ttl(ttls) => ttls.contains(ttl(log).into()),
+            Predicate::TcpFlags(tcp_flags) => tcp_flags_of(log)
+                .is_some_and(|flags| tcp_flags.iter().any(|tcp_flags| tcp_flags.matches(flags))),
+            Predicate::Rule(rules) => rules.contains(&log.packet_filter.rule_info.number),
+            Predicate::Tracker(trackers) => trackers
+                .iter()
+                .any(|tracker| tracker.eq_ignore_ascii_case(&log.packet_filter.rule_info.label)),
+        }
+    }
+}
+
+fn ports_of(log: &senpa::FwLog) -> Option<(u16, u16)> {
+    match &log.proto_info {
+        ProtoInfo::UdpInfo(udp_info) => Some((udp_info.ports.srcport, udp_info.ports.dstport)),
+        ProtoInfo::TcpInfo(tcp_info) => Some((tcp_info.ports.srcport, tcp_info.ports.dstport)),
+        ProtoInfo::UnknownInfo(_) => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TokenKind<'a> {
+    LParen,
+    RParen,
+    Eq,
+    NotEq,
+    Comma,
+    Word(&'a str),
+}
+
+impl fmt::Display for TokenKind<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::LParen => write!(f, "`(`"),
+            TokenKind::RParen => write!(f, "`)`"),
+            TokenKind::Eq => write!(f, "`=`"),
+            TokenKind::NotEq => write!(f, "`!=`"),
+            TokenKind::Comma => write!(f, "`,`"),
+            TokenKind::Word(word) => write!(f, "`{}`", word),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token<'a> {
+    kind: TokenKind<'a>,
+    position: usize,
+}
+
+impl Token<'_> {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.kind, TokenKind::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token<'_>>, ExprError> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+
+    while let Some((position, c)) = chars.next() {
+        let kind = match c {
+            c if c.is_whitespace() => continue,
+            '(' => TokenKind::LParen,
+            ')' => TokenKind::RParen,
+            '=' => TokenKind::Eq,
+            ',' => TokenKind::Comma,
+            '!' if chars.next_if(|&(_, c)| c == '=').is_some() => TokenKind::NotEq,
+            '!' => {
+                return Err(ExprError::new(
+                    position,
+                    "expected `!=`, use `not` to negate",
+                ))
+            }
+            _ => {
+                let mut end = position + c.len_utf8();
+                while let Some((next, c)) =
+                    chars.next_if(|&(_, c)| !c.is_whitespace() && !"()=,!".contains(c))
+                {
+                    end = next + c.len_utf8();
+                }
+                TokenKind::Word(&s[position..end])
+            }
+        };
+        tokens.push(Token { kind, position });
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Peekable<IntoIter<Token<'a>>>,
+    //Reported as the position of errors at the end of the expression.
+    end: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn next_if_keyword(&mut self, keyword: &str) -> bool {
+        self.tokens
+            .next_if(|token| token.is_keyword(keyword))
+            .is_some()
+    }
+
+    //`in` is only a keyword when a value follows it, `dir in` and `dir in, out`
+    //compare the direction against `in`.
+    fn next_if_in(&mut self) -> bool {
+        let mut tokens = self.tokens.clone();
+        let is_in = tokens.next().is_some_and(|token| token.is_keyword("in"));
+        let value_follows = tokens.next().is_some_and(|token| {
+            matches!(token.kind, TokenKind::Word(_))
+                && !token.is_keyword("and")
+                && !token.is_keyword("or")
+        });
+        if is_in && value_follows {
+            self.tokens.next();
+        }
+        is_in && value_follows
+    }
+
+    fn expect(&mut self, what: &str) -> Result<Token<'a>, ExprError> {
+        self.tokens
+            .next()
+            .ok_or_else(|| ExprError::new(self.end, format!("expected {}", what)))
+    }
+
+    fn or(&mut self) -> Result<Expr, ExprError> {
+        let mut expr = self.and()?;
+        while self.next_if_keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, ExprError> {
+        let mut expr = self.unary()?;
+        while self.next_if_keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ExprError> {
+        if self.next_if_keyword("not") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+
+        let token = self.expect("a field or `(`")?;
+        match token.kind {
+            TokenKind::LParen => {
+                let expr = self.or()?;
+                let close = self.expect("`)`")?;
+                if close.kind != TokenKind::RParen {
+                    return Err(ExprError::new(
+                        close.position,
+                        format!("expected `)` instead of {}", close.kind),
+                    ));
+                }
+                Ok(expr)
+            }
+            TokenKind::Word(field) => self.predicate(field, token.position),
+            kind => Err(ExprError::new(
+                token.position,
+                format!("expected a field instead of {}", kind),
+            )),
+        }
+    }
+
+    //`field value`, `field = value`, `field in value` or `field != value`,
+    //the value being a comma separated list.
+    fn predicate(&mut self, field: &str, position: usize) -> Result<Expr, ExprError> {
+        let negated = match self.tokens.peek().map(|token| &token.kind) {
+            Some(TokenKind::Eq) => {
+                self.tokens.next();
+                false
+            }
+            Some(TokenKind::NotEq) => {
+                self.tokens.next();
+                true
+            }
+            _ => {
+                self.next_if_in();
+                false
+            }
+        };
+
+        let mut values = Vec::new();
+        loop {
+            let token = self.expect(&format!("a value for `{}`", field))?;
+            let TokenKind::Word(value) = token.kind else {
+                return Err(ExprError::new(
+                    token.position,
+                    format!("expected a value for `{}` instead of {}", field, token.kind),
+                ));
+            };
+            values.push((value, token.position));
+            if self
+                .tokens
+                .next_if(|token| token.kind == TokenKind::Comma)
+                .is_none()
+            {
+                break;
+            }
+        }
+
+        let predicate = parse_predicate(field, position, &values)?;
+        Ok(match negated {
+            true => Expr::Not(Box::new(Expr::Predicate(predicate))),
+            false => Expr::Predicate(predicate),
+        })
+    }
+}
+
+fn parse_predicate(
+    field: &str,
+    position: usize,
+    values: &[(&str, usize)],
+) -> Result<Predicate, ExprError> {
+    //Parses every value, reporting the first invalid one.
+    fn parse_each<T>(
+        values: &[(&str, usize)],
+        what: &str,
+        parse: impl Fn(&str) -> Option<T>,
+    ) -> Result<Vec<T>, ExprError> {
+        values
+            .iter()
+            .map(|&(value, position)| {
+                parse(value).ok_or_else(|| {
+                    ExprError::new(position, format!("invalid {}: `{}`", what, value))
+                })
+            })
+            .collect()
+    }
+
+    let ips = |what| {
+        let mut filter = CidrIpFilter::default();
+        for ip in parse_each(values, what, |value| IpCidr::from_str(value).ok())? {
+            filter.insert(ip);
+        }
+        Ok::<_, ExprError>(filter)
+    };
+    let ports = |what| {
+        let mut filter = PortFilter::default();
+        for ports in parse_each(values, what, |value| Ports::from_str(value).ok())? {
+            filter.insert(ports);
+        }
+        Ok::<_, ExprError>(filter)
+    };
+
+    Ok(match field.to_ascii_lowercase().as_str() {
+        "action" => Predicate::Action(parse_each(values, "action", |value| {
+            Action::from_str(&value.to_lowercase()).ok()
+        })?),
+        "dir" => Predicate::Dir(parse_each(values, "direction", |value| {
+            Dir::from_str(&value.to_lowercase()).ok()
+        })?),
+        "proto" => Predicate::Proto(parse_each(values, "protocol", |value| {
+            ProtoName::from_str(&value.to_lowercase()).ok()
+        })?),
+        "iface" | "interface" => Predicate::Interface(parse_each(values, "interface", |value| {
+            Some(value.to_lowercase())
+        })?),
+        "host" => Predicate::Host(parse_each(values, "host", |value| Some(value.to_string()))?),
+        "src" => Predicate::Src(ips("source ip")?),
+        "dst" => Predicate::Dst(ips("destination ip")?),
+        "ip" => Predicate::Ip(ips("ip")?),
+        "sport" => Predicate::SrcPort(ports("source port")?),
+        "dport" => Predicate::DstPort(ports("destination port")?),
+        "port" => Predicate::Port(ports("port")?),
+        "ipver" => Predicate::IpVersion(parse_each(values, "ip version", |value| {
+            parse_ip_version(value).ok()
+        })?),
+        "len" | "length" => Predicate::Length(ports("length")?),
+        "ttl" => Predicate::Ttl(ports("ttl")?),
+        "flags" => Predicate::TcpFlags(parse_each(values, "tcp flags", |value| {
+            TcpFlags::from_str(value).ok()
+        })?),
+        "rule" => Predicate::Rule(parse_each(values, "rule", |value| value.parse().ok())?),
+        "tracker" => Predicate::Tracker(parse_each(values, "tracker", |value| {
+            Some(value.to_string())
+        })?),
+        _ => {
+            return Err(ExprError::new(
+                position,
+                format!("unknown field `{}`", field),
+            ))
+        }
+    })
+}
+
+//`4` or `6`, also accepted as `ipv4` and `ipv6`.
+pub fn parse_ip_version(value: &str) -> Result<u8, String> {
+    match value.to_ascii_lowercase().trim_start_matches("ipv") {
+        "4" => Ok(4),
+        "6" => Ok(6),
+        _ => Err(format!("invalid ip version: {}", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingesters::{
+        parser::{FilterlogParser, LogParser},
+        tests::SYSLOG_LINE,
+    };
+
+    //Passed outbound tcp from 192.168.10.15:52461 to 192.168.20.14:9100 on vlan0.20,
+    //sent by fw1.local.
+    fn test(expr: &str) -> bool {
+        let log = FilterlogParser::default().parse(SYSLOG_LINE).unwrap();
+        Expr::from_str(expr).unwrap().test(&log)
+    }
+
+    #[test]
+    fn test_predicates() {
+        assert!(test("action=pass"));
+        assert!(test("action block,PASS"));
+        assert!(!test("dir=in"));
+        assert!(!test("dir in"));
+        assert!(test("dir in, out"));
+        assert!(test("dir in in, out"));
+        assert!(test("dir in or action pass"));
+        assert!(test("proto tcp and iface vlan0.20 and host FW1.local"));
+        assert!(test("src in 192.168.10.0/24 and dst 192.168.20.14"));
+        assert!(test("ip 192.168.20.0/24 and port 9100"));
+        assert!(test("sport 50000-60000 and dport != 53,123"));
+        assert!(!test("dst in 10.0.0.0/8"));
+        assert!(test("ipver 4 and len 40-60 and ttl=127 and flags S/SA"));
+        assert!(!test("ipver=6 or ttl 1-64 or flags=SA"));
+        assert!(test(
+            "rule 95,96 and tracker FAE559338F65E11C53669FC3642C93C2"
+        ));
+        assert!(!test("rule=9 or tracker 1000000103"));
+    }
+
+    #[test]
+    fn test_precedence() {
+        //`and` binds tighter than `or`, `not` tighter than `and`.
+        assert!(test("action=block and dir=in or iface vlan0.20"));
+        assert!(!test("action=block and (dir=in or iface vlan0.20)"));
+        assert!(test("not action=block and not dport 53"));
+        assert!(!test("not (action=block or dport 9100)"));
+        assert!(test("NOT not action=pass"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |expr: &str| Expr::from_str(expr).unwrap_err();
+
+        assert_eq!(
+            error("action=block and dport"),
+            ExprError::new(22, "expected a value for `dport`")
+        );
+        assert_eq!(
+            error("action=block and port 53 iface x"),
+            ExprError::new(25, "expected `and` or `or` before `iface`")
+        );
+        assert_eq!(
+            error("(dir=in or dst 10.0.0.0/33"),
+            ExprError::new(15, "invalid destination ip: `10.0.0.0/33`")
+        );
+        assert_eq!(error("(dir=in"), ExprError::new(7, "expected `)`"));
+        assert_eq!(
+            error("colour=red"),
+            ExprError::new(0, "unknown field `colour`")
+        );
+        assert_eq!(error("dir=in and !dport 53").position, 11);
+        assert_eq!(error("dport 80,").position, 9);
+
+        assert_eq!(
+            error("dport 8o").show("dport 8o"),
+            "invalid destination port: `8o` at column 7\n  dport 8o\n        ^"
+        );
+    }
+}
@@ -0,0 +1,322 @@
+pub mod expr;
+
+use crate::cidr::{CidrIpFilter, IpCidr};
+use crate::port_filter::{PortFilter, Ports};
+use crate::tcp_flags::TcpFlags;
+use crate::TimestampedLog;
+use expr::Expr;
+use senpa::{ip::IpSpecific, Action, Dir, FwLog, ProtoInfo, ProtoName};
+
+#[derive(Debug, Default, Clone)]
+pub struct Filter {
+    actions: Option<Vec<Action>>,
+    protocols: Option<Vec<ProtoName>>,
+    interfaces: Option<Vec<String>>,
+    hosts: Option<Vec<String>>,
+    src_ips: Option<Vec<IpCidr>>,
+    dst_ips: Option<Vec<IpCidr>>,
+    src_ips_tree: CidrIpFilter,
+    dst_ips_tree: CidrIpFilter,
+    src_ports: PortFilter,
+    dst_ports: PortFilter,
+    dirs: Option<Vec<Dir>>,
+    ip_versions: Option<Vec<u8>>,
+    //Ranges of ip lengths and ttls, PortFilter works for any u16.
+    lengths: PortFilter,
+    ttls: PortFilter,
+    //Only tcp logs can match.
+    tcp_flags: Option<Vec<TcpFlags>>,
+    rules: Option<Vec<u32>>,
+    //Tracker of pfSense rules, rule id of OPNsense ones.
+    trackers: Option<Vec<String>>,
+    //Logs matching any exclusion are dropped, even when they match the lists above.
+    not_actions: Vec<Action>,
+    not_protocols: Vec<ProtoName>,
+    not_interfaces: Vec<String>,
+    not_hosts: Vec<String>,
+    not_src_ips: CidrIpFilter,
+    not_dst_ips: CidrIpFilter,
+    not_src_ports: PortFilter,
+    not_dst_ports: PortFilter,
+    expr: Option<Expr>,
+}
+
+impl Filter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_proto(&mut self, proto: ProtoName) {
+        match self.protocols {
+            Some(ref mut protocols) => protocols.push(proto),
+            None => self.protocols = Some(vec![proto]),
+        }
+    }
+
+    pub fn add_action(&mut self, action: Action) {
+        match self.actions {
+            Some(ref mut actions) => actions.push(action),
+            None => self.actions = Some(vec![action]),
+        }
+    }
+
+    pub fn add_src_ip(&mut self, ip: IpCidr) {
+        match self.src_ips {
+            Some(ref mut src) => {
+                src.push(ip.clone());
+            }
+            None => self.src_ips = Some(vec![ip.clone()]),
+        }
+        self.src_ips_tree.insert(ip);
+    }
+
+    pub fn add_dst_ip(&mut self, ip: IpCidr) {
+        match self.dst_ips {
+            Some(ref mut dst) => {
+                dst.push(ip.clone());
+            }
+            None => self.dst_ips = Some(vec![ip.clone()]),
+        }
+        self.dst_ips_tree.insert(ip);
+    }
+
+    pub fn add_src_port(&mut self, port: Ports) {
+        self.src_ports.insert(port);
+    }
+
+    pub fn add_dst_port(&mut self, port: Ports) {
+        self.dst_ports.insert(port);
+    }
+
+    pub fn add_interface(&mut self, interface: String) {
+        match self.interfaces {
+            Some(ref mut interfaces) => interfaces.push(interface),
+            None => self.interfaces = Some(vec![interface]),
+        }
+    }
+
+    //Hostnames are compared ignoring case.
+    pub fn add_host(&mut self, host: String) {
+        match self.hosts {
+            Some(ref mut hosts) => hosts.push(host),
+            None => self.hosts = Some(vec![host]),
+        }
+    }
+
+    pub fn add_dir(&mut self, dir: Dir) {
+        match self.dirs {
+            Some(ref mut dirs) => dirs.push(dir),
+            None => self.dirs = Some(vec![dir]),
+        }
+    }
+
+    pub fn add_ip_version(&mut self, version: u8) {
+        match self.ip_versions {
+            Some(ref mut versions) => versions.push(version),
+            None => self.ip_versions = Some(vec![version]),
+        }
+    }
+
+    pub fn add_length(&mut self, length: Ports) {
+        self.lengths.insert(length);
+    }
+
+    pub fn add_ttl(&mut self, ttl: Ports) {
+        self.ttls.insert(ttl);
+    }
+
+    pub fn add_tcp_flags(&mut self, flags: TcpFlags) {
+        match self.tcp_flags {
+            Some(ref mut tcp_flags) => tcp_flags.push(flags),
+            None => self.tcp_flags = Some(vec![flags]),
+        }
+    }
+
+    pub fn add_rule(&mut self, rule: u32) {
+        match self.rules {
+            Some(ref mut rules) => rules.push(rule),
+            None => self.rules = Some(vec![rule]),
+        }
+    }
+
+    //Trackers are compared ignoring case.
+    pub fn add_tracker(&mut self, tracker: String) {
+        match self.trackers {
+            Some(ref mut trackers) => trackers.push(tracker),
+            None => self.trackers = Some(vec![tracker]),
+        }
+    }
+
+    pub fn exclude_action(&mut self, action: Action) {
+        self.not_actions.push(action);
+    }
+
+    pub fn exclude_proto(&mut self, proto: ProtoName) {
+        self.not_protocols.push(proto);
+    }
+
+    pub fn exclude_interface(&mut self, interface: String) {
+        self.not_interfaces.push(interface);
+    }
+
+    pub fn exclude_host(&mut self, host: String) {
+        self.not_hosts.push(host);
+    }
+
+    pub fn exclude_src_ip(&mut self, ip: IpCidr) {
+        self.not_src_ips.insert(ip);
+    }
+
+    pub fn exclude_dst_ip(&mut self, ip: IpCidr) {
+        self.not_dst_ips.insert(ip);
+    }
+
+    pub fn exclude_src_port(&mut self, port: Ports) {
+        self.not_src_ports.insert(port);
+    }
+
+    pub fn exclude_dst_port(&mut self, port: Ports) {
+        self.not_dst_ports.insert(port);
+    }
+
+    //Logs must match the expression as well as the other criteria.
+    pub fn set_expr(&mut self, expr: Option<Expr>) {
+        self.expr = expr;
+    }
+
+    pub fn test(&self, log: &TimestampedLog) -> bool {
+        let mut ok = true;
+
+        if let Some(protocols) = &self.protocols {
+            ok &= protocols.contains(&log.log.protocol.name);
+        }
+
+        if let Some(interfaces) = &self.interfaces {
+            ok &= interfaces.contains(&log.log.packet_filter.interface);
+        }
+
+        if let Some(hosts) = &self.hosts {
+            ok &= log.header.hostname.as_ref().is_some_and(|hostname| {
+                hosts.iter().any(|host| host.eq_ignore_ascii_case(hostname))
+            });
+        }
+
+        if let Some(actions) = &self.actions {
+            ok &= actions.contains(&log.log.packet_filter.action);
+        }
+
+        if self.src_ips.is_some() {
+            ok &= self.src_ips_tree.lookup(&log.log.ip_data.src);
+        }
+
+        if self.dst_ips.is_some() {
+            ok &= self.dst_ips_tree.lookup(&log.log.ip_data.dst);
+        }
+
+        if !self.src_ports.is_empty() {
+            let port = match &log.log.proto_info {
+                senpa::ProtoInfo::UdpInfo(udp_info) => udp_info.ports.srcport,
+                senpa::ProtoInfo::TcpInfo(tcp_info) => tcp_info.ports.srcport,
+                senpa::ProtoInfo::UnknownInfo(_) => return false,
+            };
+            ok &= self.src_ports.contains(port);
+        }
+
+        if !self.dst_ports.is_empty() {
+            let port = match &log.log.proto_info {
+                senpa::ProtoInfo::UdpInfo(udp_info) => udp_info.ports.dstport,
+                senpa::ProtoInfo::TcpInfo(tcp_info) => tcp_info.ports.dstport,
+                senpa::ProtoInfo::UnknownInfo(_) => return false,
+            };
+
+            ok &= self.dst_ports.contains(port);
+        }
+
+        if let Some(dirs) = &self.dirs {
+            ok &= dirs.contains(&log.log.packet_filter.dir);
+        }
+
+        if let Some(ip_versions) = &self.ip_versions {
+            ok &= ip_versions.contains(&ip_version(&log.log));
+        }
+
+        if !self.lengths.is_empty() {
+            ok &= self.lengths.contains(log.log.ip_data.length);
+        }
+
+        if !self.ttls.is_empty() {
+            ok &= self.ttls.contains(ttl(&log.log).into());
+        }
+
+        if let Some(tcp_flags) = &self.tcp_flags {
+            ok &= tcp_flags_of(&log.log)
+                .is_some_and(|flags| tcp_flags.iter().any(|tcp_flags| tcp_flags.matches(flags)));
+        }
+
+        if let Some(rules) = &self.rules {
+            ok &= rules.contains(&log.log.packet_filter.rule_info.number);
+        }
+
+        if let Some(trackers) = &self.trackers {
+            let label = &log.log.packet_filter.rule_info.label;
+            ok &= trackers
+                .iter()
+                .any(|tracker| tracker.eq_ignore_ascii_case(label));
+        }
+
+        if let Some(expr) = &self.expr {
+            ok &= expr.test(log);
+        }
+
+        ok && !self.excluded(log)
+    }
+
+    fn excluded(&self, log: &TimestampedLog) -> bool {
+        let hostname = log.header.hostname.as_deref();
+        //Logs without ports can't match a port exclusion.
+        let ports = match &log.log.proto_info {
+            senpa::ProtoInfo::UdpInfo(udp_info) => Some(&udp_info.ports),
+            senpa::ProtoInfo::TcpInfo(tcp_info) => Some(&tcp_info.ports),
+            senpa::ProtoInfo::UnknownInfo(_) => None,
+        };
+
+        self.not_actions.contains(&log.log.packet_filter.action)
+            || self.not_protocols.contains(&log.log.protocol.name)
+            || self
+                .not_interfaces
+                .contains(&log.log.packet_filter.interface)
+            || hostname.is_some_and(|hostname| {
+                self.not_hosts
+                    .iter()
+                    .any(|host| host.eq_ignore_ascii_case(hostname))
+            })
+            || self.not_src_ips.lookup(&log.log.ip_data.src)
+            || self.not_dst_ips.lookup(&log.log.ip_data.dst)
+            || ports.is_some_and(|ports| {
+                self.not_src_ports.contains(ports.srcport)
+                    || self.not_dst_ports.contains(ports.dstport)
+            })
+    }
+}
+
+fn ip_version(log: &FwLog) -> u8 {
+    match log.ip_specific {
+        IpSpecific::IpV4(_) => 4,
+        IpSpecific::Ipv6(_) => 6,
+    }
+}
+
+//The hop limit for ipv6.
+fn ttl(log: &FwLog) -> u8 {
+    match &log.ip_specific {
+        IpSpecific::IpV4(ipv4) => ipv4.ttl,
+        IpSpecific::Ipv6(ipv6) => ipv6.hoplimit,
+    }
+}
+
+fn tcp_flags_of(log: &FwLog) -> Option<&str> {
+    match &log.proto_info {
+        ProtoInfo::TcpInfo(tcp_info) => Some(&tcp_info.flags),
+        _ => None,
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    collections::VecDeque,
+    io,
+    process::{ExitStatus, Stdio},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncBufReadExt, BufReader, Lines},
+    process::{Child, ChildStdout, Command},
+    task::JoinHandle,
+    time::{self, Instant},
+};
+
+use super::line::{LineIngester, LineSource};
+
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+//Number of stderr lines kept for display.
+const STDERR_LINES: usize = 100;
+
+pub type CommandLogIngester = LineIngester<CommandSource>;
+
+//State of the supervised command, shown in the ui.
+#[derive(Debug, Default)]
+pub struct CommandStatus {
+    pub command: String,
+    pub running: bool,
+    pub restarts: usize,
+    pub last_exit: Option<String>,
+    pub stderr: VecDeque<String>,
+}
+
+impl CommandStatus {
+    fn push_stderr(&mut self, line: String) {
+        if self.stderr.len() == STDERR_LINES {
+            self.stderr.pop_front();
+        }
+        self.stderr.push_back(line);
+    }
+}
+
+//Lines written to stdout by a shell command, started on the first read.
+//The command gets no stdin, so it doesn't compete with the ui for the terminal.
+pub struct CommandSource {
+    status: Arc<RwLock<CommandStatus>>,
+    command: String,
+    restart: bool,
+    backoff: Duration,
+    running: Option<RunningCommand>,
+}
+
+struct RunningCommand {
+    child: Child,
+    //The command runs in its own process group, led by the shell.
+    //None once the shell has been reaped, the id may then be reused by another group.
+    pgid: Option<u32>,
+    stdout: Lines<BufReader<ChildStdout>>,
+    stderr_task: JoinHandle<()>,
+    started: Instant,
+}
+
+//kill_on_drop only reaches the shell, the commands it started (e.g. ssh) are
+//killed with the rest of the group. The shell is reaped after this, so its pid still
+//holds the group id when it is killed.
+impl Drop for RunningCommand {
+    fn drop(&mut self) {
+        if let Some(pgid) = self.pgid {
+            unsafe {
+                libc::kill(-(pgid as libc::pid_t), libc::SIGKILL);
+            }
+        }
+    }
+}
+
+impl RunningCommand {
+    async fn wait(&mut self) -> io::Result<ExitStatus> {
+        let exit_status = self.child.wait().await?;
+        self.pgid = None;
+        let _ = (&mut self.stderr_task).await;
+        Ok(exit_status)
+    }
+}
+
+impl CommandSource {
+    pub fn new(command: String) -> Self {
+        Self {
+            status: Arc::new(RwLock::new(CommandStatus {
+                command: command.clone(),
+                ..Default::default()
+            })),
+            command,
+            restart: false,
+            backoff: MIN_BACKOFF,
+            running: None,
+        }
+    }
+
+    //Restart the command with exponential backoff when it exits.
+    pub fn restart(mut self, restart: bool) -> Self {
+        self.restart = restart;
+        self
+    }
+
+    pub fn get_status(&self) -> Arc<RwLock<CommandStatus>> {
+        self.status.clone()
+    }
+
+    fn spawn(&self) -> io::Result<RunningCommand> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .process_group(0)
+            .spawn()?;
+
+        if let Ok(mut status) = self.status.write() {
+            status.running = true;
+        }
+
+        let mut stderr = BufReader::new(child.stderr.take().unwrap()).lines();
+        let status = self.status.clone();
+        let stderr_task = tokio::spawn(async move {
+            while let Ok(Some(line)) = stderr.next_line().await {
+                if let Ok(mut status) = status.write() {
+                    status.push_stderr(line);
+                }
+            }
+        });
+
+        Ok(RunningCommand {
+            stdout: BufReader::new(child.stdout.take().unwrap()).lines(),
+            pgid: child.id(),
+            child,
+            stderr_task,
+            started: Instant::now(),
+        })
+    }
+
+    //Records how the command ended and waits for the backoff before it is restarted.
+    //Returns false when the command is not going to be restarted.
+    async fn exited(
+        &mut self,
+        result: io::Result<ExitStatus>,
+        started: Instant,
+    ) -> io::Result<bool> {
+        if let Ok(mut status) = self.status.write() {
+            status.running = false;
+            status.last_exit = Some(match &result {
+                Ok(exit_status) => exit_status.to_string(),
+                Err(e) => e.to_string(),
+            });
+        }
+
+        if !self.restart {
+            return result.map(|_| false);
+        }
+
+        //A command that ran for a while is not failing in a loop.
+        if started.elapsed() >= MAX_BACKOFF {
+            self.backoff = MIN_BACKOFF;
+        }
+        time::sleep(self.backoff).await;
+        self.backoff = (self.backoff * 2).min(MAX_BACKOFF);
+
+        if let Ok(mut status) = self.status.write() {
+            status.restarts += 1;
+        }
+        Ok(true)
+    }
+}
+
+impl LineSource for CommandSource {
+    async fn next_line(&mut self) -> io::Result<Option<String>> {
+        loop {
+            let (result, started) = match &mut self.running {
+                Some(running) => {
+                    let result = match running.stdout.next_line().await {
+                        Ok(Some(raw_log)) => return Ok(Some(raw_log)),
+                        Ok(None) => running.wait().await,
+                        Err(e) => Err(e),
+                    };
+                    (result, running.started)
+                }
+                None => match self.spawn() {
+                    Ok(running) => {
+                        self.running = Some(running);
+                        continue;
+                    }
+                    Err(e) => (Err(e), Instant::now()),
+                },
+            };
+
+            self.running = None;
+            if !self.exited(result, started).await? {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingesters::{tests::SYSLOG_LINE, LogIngester};
+
+    #[tokio::test]
+    async fn test_command_ingest() {
+        let command = format!(
+            "echo '{}'; echo garbage; echo oops >&2; exit 3",
+            SYSLOG_LINE
+        );
+        let source = CommandSource::new(command);
+        let status = source.get_status();
+        let mut ingester = CommandLogIngester::new(source);
+        ingester.ingest().await.unwrap();
+
+        assert_eq!(ingester.get_logs().read().unwrap().len(), 1);
+
+        let status = status.read().unwrap();
+        assert!(!status.running);
+        assert_eq!(status.last_exit.as_deref(), Some("exit status: 3"));
+        assert_eq!(status.stderr, ["oops"]);
+    }
+
+    #[tokio::test]
+    async fn test_command_restart() {
+        let source = CommandSource::new("echo restarted >&2".into()).restart(true);
+        let status = source.get_status();
+        let mut ingester = CommandLogIngester::new(source);
+        tokio::spawn(async move { ingester.ingest().await });
+
+        time::timeout(Duration::from_secs(5), async {
+            while status.read().unwrap().restarts < 1 {
+                time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        assert!(!status.read().unwrap().stderr.is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_kill_process_group() {
+        //The shell prints the pid of a command it started in the background.
+        let mut source = CommandSource::new("sleep 60 & echo $!; wait".into());
+        let pid = source.next_line().await.unwrap().unwrap();
+        drop(source);
+
+        //A killed process can stay a zombie until init reaps it.
+        let stat = std::path::Path::new("/proc").join(pid).join("stat");
+        time::timeout(Duration::from_secs(5), async {
+            while std::fs::read_to_string(&stat).is_ok_and(|stat| !stat.contains(") Z")) {
+                time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reaped_group_not_killed() {
+        let mut running = CommandSource::new("exit 0".into()).spawn().unwrap();
+        assert!(running.pgid.is_some());
+        running.wait().await.unwrap();
+        assert_eq!(running.pgid, None);
+    }
+}
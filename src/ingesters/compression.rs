@@ -0,0 +1,107 @@
+use async_compression::tokio::bufread::{BzDecoder, GzipDecoder, XzDecoder, ZstdDecoder};
+use tokio::io::{AsyncBufRead, AsyncRead};
+
+//Compression formats used for rotated log archives.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    //Detects the compression format from the magic bytes at the start of a file,
+    //the file extension is not taken into account.
+    pub fn detect(header: &[u8]) -> Option<Self> {
+        match header {
+            [0x1f, 0x8b, ..] => Some(Compression::Gzip),
+            [b'B', b'Z', b'h', ..] => Some(Compression::Bzip2),
+            [0xfd, b'7', b'z', b'X', b'Z', 0x00, ..] => Some(Compression::Xz),
+            [0x28, 0xb5, 0x2f, 0xfd, ..] => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    //Wraps `reader` into a streaming decoder, concatenated archives are read as a whole.
+    pub fn decoder<'a, R: AsyncBufRead + Unpin + Send + 'a>(
+        self,
+        reader: R,
+    ) -> Box<dyn AsyncRead + Unpin + Send + 'a> {
+        match self {
+            Compression::Gzip => {
+                let mut decoder = GzipDecoder::new(reader);
+                decoder.multiple_members(true);
+                Box::new(decoder)
+            }
+            Compression::Bzip2 => {
+                let mut decoder = BzDecoder::new(reader);
+                decoder.multiple_members(true);
+                Box::new(decoder)
+            }
+            Compression::Xz => {
+                let mut decoder = XzDecoder::new(reader);
+                decoder.multiple_members(true);
+                Box::new(decoder)
+            }
+            Compression::Zstd => {
+                let mut decoder = ZstdDecoder::new(reader);
+                decoder.multiple_members(true);
+                Box::new(decoder)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_compression::tokio::write::{BzEncoder, GzipEncoder, XzEncoder, ZstdEncoder};
+    use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+    async fn compress<W: AsyncWrite + Unpin>(mut encoder: W, data: &[u8]) {
+        encoder.write_all(data).await.unwrap();
+        encoder.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let data = b"first line\nsecond line\n";
+
+        let mut gzip = GzipEncoder::new(Vec::new());
+        compress(&mut gzip, data).await;
+        let mut bzip2 = BzEncoder::new(Vec::new());
+        compress(&mut bzip2, data).await;
+        let mut xz = XzEncoder::new(Vec::new());
+        compress(&mut xz, data).await;
+        let mut zstd = ZstdEncoder::new(Vec::new());
+        compress(&mut zstd, data).await;
+
+        for (compressed, expected) in [
+            (gzip.into_inner(), Compression::Gzip),
+            (bzip2.into_inner(), Compression::Bzip2),
+            (xz.into_inner(), Compression::Xz),
+            (zstd.into_inner(), Compression::Zstd),
+        ] {
+            assert_eq!(Compression::detect(&compressed), Some(expected));
+
+            let mut decompressed = Vec::new();
+            expected
+                .decoder(compressed.as_slice())
+                .read_to_end(&mut decompressed)
+                .await
+                .unwrap();
+            assert_eq!(decompressed, data);
+        }
+    }
+
+    #[test]
+    fn test_detect_plain_text() {
+        assert_eq!(
+            Compression::detect(b"<134>1 2025-01-02T12:00:00+01:00"),
+            None
+        );
+        assert_eq!(Compression::detect(b""), None);
+        assert_eq!(Compression::detect(&[0x1f]), None);
+    }
+}
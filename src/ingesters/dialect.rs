@@ -0,0 +1,159 @@
+use clap::ValueEnum;
+use senpa::{ip::IpSpecific, log::LogParseError, parse_log, FwLog, ProtoInfo};
+
+//Flavour of filterlog written by the firewall.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Dialect {
+    /// Try opnsense first, then pfsense
+    #[default]
+    Auto,
+    Opnsense,
+    Pfsense,
+}
+
+impl Dialect {
+    //Parses the filterlog CSV payload of a message.
+    pub fn parse(self, msg: &str) -> Result<FwLog, LogParseError> {
+        match self {
+            Dialect::Opnsense => parse_log(msg),
+            Dialect::Pfsense => parse_pfsense(msg),
+            Dialect::Auto => parse_log(msg).or_else(|e| parse_pfsense(msg).map_err(|_| e)),
+        }
+    }
+}
+
+//pf reason of a filterlog payload, as written. pfSense also logs reasons other than
+//`match`, which the parsed log can't represent.
+pub fn reason(msg: &str) -> &str {
+    msg.split(',').nth(5).unwrap_or_default()
+}
+
+fn is_alphanumeric(field: &str) -> bool {
+    field.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+//pfSense shares the field layout of opnsense, but its values don't always fit the
+//grammar of the parser: anchors may contain `/` or `_`, trackers and ecn may be empty
+//or free text, pf reasons other than `match` are logged and protocols other than
+//tcp and udp carry several trailing fields (icmp type and id, carp vhid...).
+//Those fields are replaced with values the parser accepts, then the original values
+//are put back in the parsed log.
+fn parse_pfsense(msg: &str) -> Result<FwLog, LogParseError> {
+    let mut fields: Vec<&str> = msg.split(',').collect();
+
+    //Index of the ecn, the protocol name and the first protocol specific field.
+    let (ecn_index, proto_index, info_index) = match fields.get(8) {
+        Some(&"4") => (Some(10), 16, 20),
+        Some(&"6") => (None, 12, 17),
+        _ => return parse_log(msg),
+    };
+    if fields.len() < info_index {
+        return parse_log(msg);
+    }
+
+    let anchor = fields[2];
+    if !is_alphanumeric(anchor) {
+        fields[2] = "";
+    }
+    let tracker = fields[3];
+    if tracker.is_empty() || !is_alphanumeric(tracker) {
+        fields[3] = "0";
+    }
+    //The parsed log can only represent `match`, the original is kept by reason().
+    fields[5] = "match";
+
+    let ecn = ecn_index.map(|index| fields[index]);
+    if let Some(index) = ecn_index {
+        if !is_alphanumeric(fields[index]) {
+            fields[index] = "";
+        }
+    }
+
+    let info = match fields[proto_index] {
+        "tcp" | "udp" => None,
+        _ => {
+            let info = fields[info_index..].join(",");
+            fields.truncate(info_index);
+            fields.push("");
+            Some(info)
+        }
+    };
+
+    let mut log = parse_log(&fields.join(",")).map_err(|e| LogParseError {
+        raw_log: msg.into(),
+        reason: e.reason,
+    })?;
+
+    let rule_info = &mut log.packet_filter.rule_info;
+    rule_info.anchorname = (!anchor.is_empty()).then(|| anchor.into());
+    rule_info.label = tracker.into();
+    if let (IpSpecific::IpV4(ipv4), Some(ecn)) = (&mut log.ip_specific, ecn) {
+        ipv4.ecn = (!ecn.is_empty()).then(|| ecn.into());
+    }
+    if let Some(info) = info {
+        log.proto_info = ProtoInfo::UnknownInfo(info);
+    }
+
+    Ok(log)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PFSENSE_ICMP: &str = "5,,,1000000103,igb1,match,block,in,4,0x0,,64,0,0,none,1,icmp,\
+        84,192.168.1.10,8.8.8.8,request,12345,1";
+
+    #[test]
+    fn test_pfsense_trailing_fields() {
+        assert!(Dialect::Opnsense.parse(PFSENSE_ICMP).is_err());
+
+        let log = Dialect::Pfsense.parse(PFSENSE_ICMP).unwrap();
+        assert_eq!(log.packet_filter.rule_info.label, "1000000103");
+        assert_eq!(log.packet_filter.interface, "igb1");
+        assert_eq!(
+            log.proto_info,
+            ProtoInfo::UnknownInfo("request,12345,1".into())
+        );
+        assert_eq!(Dialect::Auto.parse(PFSENSE_ICMP), Ok(log));
+    }
+
+    #[test]
+    fn test_pfsense_quirks() {
+        let log = Dialect::Pfsense
+            .parse(
+                "9,,user_rules/lan,,em0,bad-offset,block,in,4,0x0,ECT(0),64,0,0,DF,17,udp,\
+                 328,192.168.1.1,255.255.255.255,68,67,308",
+            )
+            .unwrap();
+        assert_eq!(
+            log.packet_filter.rule_info.anchorname.as_deref(),
+            Some("user_rules/lan")
+        );
+        assert_eq!(log.packet_filter.rule_info.label, "");
+        match log.ip_specific {
+            IpSpecific::IpV4(ipv4) => assert_eq!(ipv4.ecn.as_deref(), Some("ECT(0)")),
+            _ => panic!("expected an ipv4 log"),
+        }
+
+        let log = Dialect::Pfsense
+            .parse(
+                "10,,,1000000105,igb0,match,pass,out,6,0x00,0x00000,255,ipv6-icmp,58,\
+                 32,fe80::1,ff02::1,neighbor-advertisement",
+            )
+            .unwrap();
+        assert_eq!(
+            log.proto_info,
+            ProtoInfo::UnknownInfo("neighbor-advertisement".into())
+        );
+    }
+
+    #[test]
+    fn test_opnsense_unchanged() {
+        let opnsense = "96,,,fae559338f65e11c53669fc3642c93c2,vlan0.20,match,pass,out,\
+            4,0x0,,127,61633,0,DF,6,tcp,52,192.168.10.15,192.168.20.14,\
+            52461,9100,0,S,3442468761,,64240,,mss;nop;wscale;nop;nop;sackOK";
+        assert_eq!(Dialect::Pfsense.parse(opnsense), parse_log(opnsense));
+        assert!(Dialect::Pfsense.parse("garbage").is_err());
+    }
+}
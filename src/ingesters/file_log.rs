@@ -1,81 +1,258 @@
-use chrono::DateTime;
-use rsyslog::Message;
 use std::{
-    io::{self},
+    io::{self, SeekFrom},
+    os::unix::fs::MetadataExt,
     path::{Path, PathBuf},
-    str::FromStr,
-    sync::{Arc, RwLock},
+    time::Duration,
 };
 use tokio::{
-    io::{AsyncBufReadExt, BufReader},
-    sync::Notify,
+    io::{AsyncBufReadExt, AsyncRead, AsyncSeekExt, BufReader, Lines},
+    time,
 };
 
-use senpa::parse_log;
 use tokio::fs;
 
-use crate::TimestampedLog;
+use super::{
+    compression::Compression,
+    line::{LineIngester, LineSource},
+};
+
+//How often a followed file is checked for new data once EOF is reached.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(250);
 
-use super::LogIngester;
+pub type FileLogIngester = LineIngester<FileSource>;
 
-pub struct FileLogIngester {
-    pub logs: Arc<RwLock<Vec<TimestampedLog>>>,
-    pub notify: Arc<Notify>,
+//Lines of a log file, opened on the first read.
+pub struct FileSource {
     path: PathBuf,
+    follow: bool,
+    reader: Option<FileReader>,
 }
 
-impl LogIngester for FileLogIngester {
-    async fn ingest(&mut self) -> io::Result<()> {
-        let file = fs::File::open(&self.path).await?;
+enum FileReader {
+    //Compressed logs are rotated archives, there is nothing to follow.
+    Compressed(Lines<BufReader<Box<dyn AsyncRead + Unpin + Send>>>),
+    Plain {
+        reader: BufReader<fs::File>,
+        inode: u64,
+        //Bytes consumed from the current file, used to detect truncation.
+        position: u64,
+    },
+}
 
-        let reader = BufReader::new(file);
-        let mut lines = reader.lines();
+impl FileSource {
+    pub async fn new<T: AsRef<Path>>(path: T) -> Result<Self, io::Error> {
+        match fs::try_exists(&path).await? {
+            true => Ok(Self {
+                path: path.as_ref().to_path_buf(),
+                follow: false,
+                reader: None,
+            }),
+            false => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("the file {} doesn't exist", path.as_ref().to_str().unwrap()),
+            )),
+        }
+    }
 
-        let logs = self.logs.clone();
-        while let Some(raw_log) = lines.next_line().await? {
-            let rsys_log: Result<Message, _> = rsyslog::Message::parse(&raw_log);
+    //Keep reading after EOF, like `tail -F`.
+    pub fn follow(mut self, follow: bool) -> Self {
+        self.follow = follow;
+        self
+    }
+}
 
-            if let Ok(msg) = rsys_log {
-                let opnsense_raw_log = msg.msg.msg;
+impl LineSource for FileSource {
+    async fn next_line(&mut self) -> io::Result<Option<String>> {
+        if self.reader.is_none() {
+            self.reader = Some(FileReader::open(&self.path).await?);
+        }
+
+        let (reader, inode, position) = match self.reader.as_mut().unwrap() {
+            FileReader::Compressed(lines) => return lines.next_line().await,
+            FileReader::Plain {
+                reader,
+                inode,
+                position,
+            } => (reader, inode, position),
+        };
 
-                if let Ok(flog) = parse_log(opnsense_raw_log) {
-                    let timestamped_log = TimestampedLog {
-                        log: flog,
-                        timestamp: DateTime::from_str(msg.timestamp.unwrap()).unwrap(),
-                    };
+        let mut raw_log = String::new();
+        //Set once the file has been replaced, the old one is then read to EOF again.
+        let mut rotated = false;
 
-                    if let Ok(mut lock_guard) = logs.write() {
-                        lock_guard.push(timestamped_log);
-                    }
+        loop {
+            let read = reader.read_line(&mut raw_log).await?;
+            *position += read as u64;
 
-                    //notify packet filter
-                    self.notify.notify_one();
+            if read > 0 {
+                //While following, a line without newline may still be being written.
+                if raw_log.ends_with('\n') || !self.follow {
+                    return Ok(Some(trim_line(raw_log)));
                 }
+                continue;
+            }
+
+            if !self.follow {
+                return Ok(None);
+            }
+
+            if rotated {
+                //The old file has been read to EOF, continue with the new one.
+                let file = fs::File::open(&self.path).await?;
+                *inode = file.metadata().await?.ino();
+                *reader = BufReader::new(file);
+                rotated = false;
+            } else {
+                time::sleep(FOLLOW_INTERVAL).await;
+
+                let Ok(metadata) = fs::metadata(&self.path).await else {
+                    //The file has been moved away and not yet recreated.
+                    continue;
+                };
+
+                if metadata.ino() != *inode {
+                    //Rotated: lines may have been appended to the old file during the sleep.
+                    rotated = true;
+                    continue;
+                } else if metadata.len() < *position {
+                    //Truncated in place.
+                    reader.seek(SeekFrom::Start(0)).await?;
+                } else {
+                    continue;
+                }
+            }
+
+            *position = 0;
+            if !raw_log.is_empty() {
+                return Ok(Some(trim_line(raw_log)));
             }
         }
-        Ok(())
     }
+}
 
-    fn get_notify(&self) -> Arc<Notify> {
-        self.notify.clone()
-    }
+fn trim_line(mut raw_log: String) -> String {
+    let len = raw_log.trim_end_matches(['\n', '\r']).len();
+    raw_log.truncate(len);
+    raw_log
+}
+
+impl FileReader {
+    async fn open(path: &Path) -> io::Result<Self> {
+        let file = fs::File::open(path).await?;
+        let inode = file.metadata().await?.ino();
+        let mut reader = BufReader::new(file);
 
-    fn get_logs(&self) -> Arc<RwLock<Vec<TimestampedLog>>> {
-        self.logs.clone()
+        Ok(match Compression::detect(reader.fill_buf().await?) {
+            Some(compression) => {
+                FileReader::Compressed(BufReader::new(compression.decoder(reader)).lines())
+            }
+            None => FileReader::Plain {
+                reader,
+                inode,
+                position: 0,
+            },
+        })
     }
 }
-impl FileLogIngester {
-    pub async fn new<T: AsRef<Path>>(path: T) -> Result<Self, io::Error> {
-        match fs::try_exists(&path).await? {
-            true => Ok(Self {
-                logs: Arc::new(RwLock::new(Vec::new())),
-                notify: Arc::new(Notify::new()),
-                path: path.as_ref().to_path_buf(),
-            }),
-            false => Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                format!("the file {} doesn't exist", path.as_ref().to_str().unwrap()),
-            )),
-        }
+
+//Opens a log file line by line, compressed files are decompressed on the fly.
+pub(crate) async fn open_lines(
+    path: &Path,
+) -> io::Result<Lines<BufReader<Box<dyn AsyncRead + Unpin + Send>>>> {
+    Ok(BufReader::new(open_reader(path).await?).lines())
+}
+
+//Opens a log file, compressed files are decompressed on the fly.
+pub(crate) async fn open_reader(path: &Path) -> io::Result<Box<dyn AsyncRead + Unpin + Send>> {
+    let mut reader = BufReader::new(fs::File::open(path).await?);
+
+    Ok(match Compression::detect(reader.fill_buf().await?) {
+        Some(compression) => compression.decoder(reader),
+        None => Box::new(reader),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ingesters::{
+            tests::{TempDir, SYSLOG_LINE},
+            LogIngester,
+        },
+        store::LogStore,
+    };
+    use std::{
+        fs::OpenOptions,
+        io::Write,
+        sync::{Arc, RwLock},
+    };
+
+    async fn wait_for_logs(logs: &Arc<RwLock<LogStore>>, count: usize) {
+        time::timeout(Duration::from_secs(5), async {
+            while logs.read().unwrap().len() < count {
+                time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_follow_rotation() {
+        let dir = TempDir::new("follow");
+        let path = dir.join("latest.log");
+        std::fs::write(&path, format!("{}\n", SYSLOG_LINE)).unwrap();
+
+        let mut ingester = FileLogIngester::new(FileSource::new(&path).await.unwrap().follow(true));
+        let logs = ingester.get_logs();
+        tokio::spawn(async move { ingester.ingest().await });
+        wait_for_logs(&logs, 1).await;
+
+        //Append, with the line written in two parts.
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "{}", &SYSLOG_LINE[..20]).unwrap();
+        file.flush().unwrap();
+        time::sleep(FOLLOW_INTERVAL * 2).await;
+        writeln!(file, "{}", &SYSLOG_LINE[20..]).unwrap();
+        wait_for_logs(&logs, 2).await;
+
+        //Append a last line, rename and recreate before the next check.
+        writeln!(file, "{}", SYSLOG_LINE).unwrap();
+        std::fs::rename(&path, dir.join("rotated.log")).unwrap();
+        std::fs::write(&path, format!("{}\n", SYSLOG_LINE)).unwrap();
+        wait_for_logs(&logs, 4).await;
+
+        //Truncate.
+        time::sleep(FOLLOW_INTERVAL * 2).await;
+        std::fs::write(&path, "").unwrap();
+        time::sleep(FOLLOW_INTERVAL * 2).await;
+        std::fs::write(&path, format!("{}\n", SYSLOG_LINE)).unwrap();
+        wait_for_logs(&logs, 5).await;
+
+        time::sleep(FOLLOW_INTERVAL * 2).await;
+        assert_eq!(logs.read().unwrap().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_compressed_file() {
+        use async_compression::tokio::write::GzipEncoder;
+        use tokio::io::AsyncWriteExt;
+
+        let mut encoder = GzipEncoder::new(Vec::new());
+        encoder
+            .write_all(format!("{}\n{}\n", SYSLOG_LINE, SYSLOG_LINE).as_bytes())
+            .await
+            .unwrap();
+        encoder.shutdown().await.unwrap();
+
+        //No .gz extension, the format is detected from the content.
+        let dir = TempDir::new("gzip");
+        let path = dir.join("filter.log");
+        std::fs::write(&path, encoder.into_inner()).unwrap();
+
+        let mut ingester = FileLogIngester::new(FileSource::new(&path).await.unwrap());
+        ingester.ingest().await.unwrap();
+        assert_eq!(ingester.get_logs().read().unwrap().len(), 2);
     }
 }
@@ -0,0 +1,99 @@
+use tokio::io::{self, AsyncBufRead, Lines};
+
+use super::{LogIngester, LogSink};
+
+//A transport yielding raw lines, each one carrying a single log message.
+#[allow(async_fn_in_trait)]
+pub trait LineSource {
+    //Returns the next line without its terminator, None once the source is exhausted.
+    async fn next_line(&mut self) -> io::Result<Option<String>>;
+}
+
+impl<R: AsyncBufRead + Unpin> LineSource for Lines<R> {
+    async fn next_line(&mut self) -> io::Result<Option<String>> {
+        Lines::next_line(self).await
+    }
+}
+
+//Reads logs from any line source, the lines are parsed by the sink's parser.
+pub struct LineIngester<S> {
+    source: S,
+    sink: LogSink,
+}
+
+impl<S: LineSource> LineIngester<S> {
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            sink: LogSink::default(),
+        }
+    }
+}
+
+impl<S: LineSource> LogIngester for LineIngester<S> {
+    async fn ingest(&mut self) -> io::Result<()> {
+        self.sink.ingest(&mut self.source).await
+    }
+
+    fn sink(&self) -> &LogSink {
+        &self.sink
+    }
+
+    fn sink_mut(&mut self) -> &mut LogSink {
+        &mut self.sink
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ingesters::{
+            parser::{FilterlogParser, LogParser, ParseError},
+            tests::SYSLOG_LINE,
+        },
+        TimestampedLog,
+    };
+    use std::sync::Arc;
+    use tokio::io::AsyncBufReadExt;
+
+    //Only accepts the filterlog payload prefixed with `ok`.
+    struct PrefixParser;
+
+    impl LogParser for PrefixParser {
+        fn parse(&self, raw_log: &str) -> Result<TimestampedLog, ParseError> {
+            let payload = raw_log
+                .strip_prefix("ok ")
+                .ok_or(ParseError::SyslogHeader)?;
+            FilterlogParser::default().parse(payload)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_parser() {
+        let input = format!("ok {}\n{}\n\nok 1,garbage\n", SYSLOG_LINE, SYSLOG_LINE);
+        let mut ingester = LineIngester::new(input.as_bytes().lines());
+        ingester.sink_mut().set_parser(Arc::new(PrefixParser));
+        ingester.ingest().await.unwrap();
+
+        assert_eq!(ingester.get_logs().read().unwrap().len(), 1);
+
+        let rejects = ingester.get_rejects();
+        let rejects = rejects.read().unwrap();
+        assert_eq!(rejects.syslog_header, 1);
+        assert_eq!(rejects.filterlog, 1);
+    }
+
+    #[tokio::test]
+    async fn test_ingest_with_limits() {
+        let input = format!("{}\n", SYSLOG_LINE).repeat(5);
+        let mut ingester = LineIngester::new(input.as_bytes().lines());
+        ingester.sink_mut().set_limits(Some(2), None);
+        ingester.ingest().await.unwrap();
+
+        let logs = ingester.get_logs();
+        let logs = logs.read().unwrap();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs.first(), 3);
+    }
+}
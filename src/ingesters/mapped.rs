@@ -0,0 +1,302 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use memmap2::Mmap;
+use tokio::task;
+
+use crate::{
+    store::{
+        mapped::{LineIndex, MappedLogs},
+        LogStore,
+    },
+    timestamp::TimeWindow,
+};
+
+use super::{
+    compression::Compression,
+    parser::{LogParser, ParseError},
+    LogIngester, LogSink,
+};
+
+//Bytes indexed at once, the filter is notified after each chunk.
+const CHUNK_SIZE: usize = 8 << 20;
+
+//Indexes a memory mapped log file instead of parsing it, the logs are parsed
+//when they are shown or filtered.
+pub struct MappedFileIngester {
+    sink: LogSink,
+    path: PathBuf,
+    mmap: Arc<Mmap>,
+    cache: bool,
+}
+
+impl MappedFileIngester {
+    pub fn new<T: AsRef<Path>>(path: T) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = File::open(&path)?;
+
+        let mut header = Vec::with_capacity(6);
+        (&mut file).take(6).read_to_end(&mut header)?;
+        if Compression::detect(&header).is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is compressed and can't be mapped", path.display()),
+            ));
+        }
+
+        //SAFETY: reading the mapping past the end of a truncated file raises SIGBUS,
+        //--lazy requires a file that is only appended to or rotated by renaming it.
+        let mmap = Arc::new(unsafe { Mmap::map(&file)? });
+        let mut ingester = Self {
+            sink: LogSink::default(),
+            path,
+            mmap,
+            cache: false,
+        };
+        ingester.set_store();
+        Ok(ingester)
+    }
+
+    //Reuse the index cached beside the file, or cache it once built.
+    pub fn cache(mut self, cache: bool) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    fn set_store(&mut self) {
+        let logs = MappedLogs::new(self.mmap.clone(), self.sink.parser.clone());
+        self.sink.logs = Arc::new(RwLock::new(LogStore::mapped(logs)));
+    }
+
+    fn append(&self, index: LineIndex) {
+        if let Some(logs) = self.sink.logs.write().unwrap().mapped_logs_mut() {
+            logs.append(index);
+        }
+        self.sink.notify.notify_one();
+    }
+}
+
+impl LogIngester for MappedFileIngester {
+    async fn ingest(&mut self) -> io::Result<()> {
+        //The parser may have been set since the store was created.
+        if let Some(logs) = self.sink.logs.write().unwrap().mapped_logs_mut() {
+            logs.set_parser(self.sink.parser.clone());
+        }
+
+        let metadata = std::fs::metadata(&self.path)?;
+        let cache_path = LineIndex::cache_path(&self.path);
+        if self.cache {
+            if let Ok(Some(index)) = LineIndex::load(&cache_path, &metadata) {
+                self.append(index.within(&self.sink.window));
+                return Ok(());
+            }
+        }
+
+        let mut start = 0;
+        while start < self.mmap.len() {
+            let mmap = self.mmap.clone();
+            let parser = self.sink.parser.clone();
+            let window = self.sink.window;
+            let chunk = task::spawn_blocking(move || {
+                index_chunk(&mmap, start, CHUNK_SIZE, &*parser, &window)
+            })
+            .await
+            .map_err(io::Error::other)?;
+
+            for (raw_log, reason) in chunk.rejects {
+                self.sink.reject(&raw_log, reason);
+            }
+            self.append(chunk.index);
+            if chunk.past {
+                break;
+            }
+            start = chunk.end;
+        }
+
+        //Only the complete index can be reused.
+        if self.cache && self.sink.window.is_unbounded() {
+            if let Some(logs) = self.sink.logs.read().unwrap().mapped_logs() {
+                //Without a cache the next opening is only slower.
+                let _ = logs.index().save(&cache_path, &metadata);
+            }
+        }
+        Ok(())
+    }
+
+    fn sink(&self) -> &LogSink {
+        &self.sink
+    }
+
+    fn sink_mut(&mut self) -> &mut LogSink {
+        &mut self.sink
+    }
+}
+
+struct Chunk {
+    index: LineIndex,
+    rejects: Vec<(String, ParseError)>,
+    //Where the next chunk starts.
+    end: usize,
+    //A line past the end of the time window has been found, the file is sorted.
+    past: bool,
+}
+
+//Indexes the lines within `window` starting from `start` up to the first line end
+//after `size` bytes.
+fn index_chunk(
+    data: &[u8],
+    start: usize,
+    size: usize,
+    parser: &dyn LogParser,
+    window: &TimeWindow,
+) -> Chunk {
+    let limit = start.saturating_add(size).min(data.len());
+    let mut chunk = Chunk {
+        index: LineIndex::default(),
+        rejects: Vec::new(),
+        end: match memchr::memchr(b'\n', &data[limit..]) {
+            Some(newline) => limit + newline + 1,
+            None => data.len(),
+        },
+        past: false,
+    };
+
+    let mut line_start = start;
+    for line in data[start..chunk.end].split_inclusive(|&b| b == b'\n') {
+        let offset = line_start;
+        line_start += line.len();
+
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let Ok(line) = std::str::from_utf8(line) else {
+            chunk.rejects.push((
+                String::from_utf8_lossy(line).into_owned(),
+                ParseError::SyslogHeader,
+            ));
+            continue;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match parser.timestamp(line) {
+            Ok(timestamp) if window.is_past(&timestamp) => {
+                chunk.past = true;
+                break;
+            }
+            Ok(timestamp) if window.contains(&timestamp) => {
+                chunk.index.push(offset as u64, timestamp)
+            }
+            Ok(_) => {}
+            Err(reason) => chunk.rejects.push((line.to_string(), reason)),
+        }
+    }
+
+    chunk
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingesters::{
+        parser::FilterlogParser,
+        tests::{syslog_line_at, TempDir, SYSLOG_LINE},
+    };
+    use chrono::{DateTime, Local};
+
+    #[test]
+    fn test_index_chunk() {
+        let data = format!("{}\n\ngarbage\n{}\n", SYSLOG_LINE, SYSLOG_LINE);
+        let parser = FilterlogParser::default();
+        let window = TimeWindow::default();
+
+        //The first chunk ends with the line crossing the chunk size.
+        let chunk = index_chunk(data.as_bytes(), 0, 10, &parser, &window);
+        assert_eq!(chunk.index.len(), 1);
+        assert!(chunk.rejects.is_empty());
+        assert_eq!(chunk.end, SYSLOG_LINE.len() + 1);
+
+        let chunk = index_chunk(data.as_bytes(), chunk.end, 0, &parser, &window);
+        assert_eq!(chunk.index.len(), 0);
+        assert_eq!(chunk.end, SYSLOG_LINE.len() + 2);
+        assert!(chunk.rejects.is_empty());
+
+        let chunk = index_chunk(data.as_bytes(), chunk.end, 1000, &parser, &window);
+        assert_eq!(chunk.index.len(), 1);
+        assert_eq!(
+            chunk.rejects[0],
+            ("garbage".to_string(), ParseError::SyslogHeader)
+        );
+        assert_eq!(chunk.end, data.len());
+        assert!(!chunk.past);
+    }
+
+    #[test]
+    fn test_index_window() {
+        let data: String = (10..15)
+            .map(|hour| {
+                format!(
+                    "{}\n",
+                    syslog_line_at(&format!("2025-01-02T{}:00:00Z", hour))
+                )
+            })
+            .collect();
+        let at = |hour| {
+            DateTime::parse_from_rfc3339(&format!("2025-01-02T{}:00:00Z", hour))
+                .unwrap()
+                .with_timezone(&Local)
+        };
+        let window = TimeWindow {
+            since: Some(at(11)),
+            until: Some(at(12)),
+        };
+
+        let chunk = index_chunk(
+            data.as_bytes(),
+            0,
+            data.len(),
+            &FilterlogParser::default(),
+            &window,
+        );
+        assert_eq!(chunk.index.len(), 2);
+        assert_eq!(chunk.index.timestamp(0), Some(at(11)));
+        assert!(chunk.past);
+    }
+
+    #[tokio::test]
+    async fn test_mapped_file() {
+        let dir = TempDir::new("lazy");
+        let path = dir.join("filter.log");
+        std::fs::write(
+            &path,
+            format!(
+                "{}\ngarbage\n{}\n",
+                syslog_line_at("2025-01-02T11:00:00Z"),
+                SYSLOG_LINE
+            ),
+        )
+        .unwrap();
+
+        let mut ingester = MappedFileIngester::new(&path).unwrap().cache(true);
+        ingester.ingest().await.unwrap();
+        {
+            let logs = ingester.get_logs();
+            let logs = logs.read().unwrap();
+            assert_eq!(logs.len(), 2);
+            assert_eq!(logs.timestamp(0), Some(logs.get(0).unwrap().timestamp));
+            assert_eq!(logs.get(1).unwrap().log.packet_filter.interface, "vlan0.20");
+        }
+        assert_eq!(ingester.get_rejects().read().unwrap().total(), 1);
+        assert!(LineIndex::cache_path(&path).exists());
+
+        //The cached index is used, the rejected line is not read again.
+        let mut ingester = MappedFileIngester::new(&path).unwrap().cache(true);
+        ingester.ingest().await.unwrap();
+        assert_eq!(ingester.get_logs().read().unwrap().len(), 2);
+        assert_eq!(ingester.get_rejects().read().unwrap().total(), 0);
+    }
+}
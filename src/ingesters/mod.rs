@@ -1,14 +1,293 @@
-use std::sync::{Arc, RwLock};
+use std::{
+    fmt,
+    fs::File,
+    net::SocketAddr,
+    str::FromStr,
+    sync::{Arc, RwLock},
+};
 
 use tokio::{io, sync::Notify};
 
-use crate::TimestampedLog;
+use crate::{store::LogStore, timestamp::TimeWindow, TimestampedLog};
 
+use line::LineSource;
+use parser::{FilterlogParser, LogParser, ParseError};
+use rejects::RejectedLogs;
+use tee::TeeWriter;
+
+//Ingesters are spawned on the tokio runtime by main, which checks the futures are Send.
+#[allow(async_fn_in_trait)]
 pub trait LogIngester {
     async fn ingest(&mut self) -> io::Result<()>;
-    fn get_notify(&self) -> Arc<Notify>;
-    fn get_logs(&self) -> Arc<RwLock<Vec<TimestampedLog>>>;
+    fn sink(&self) -> &LogSink;
+    fn sink_mut(&mut self) -> &mut LogSink;
+
+    fn get_notify(&self) -> Arc<Notify> {
+        self.sink().notify.clone()
+    }
+
+    fn get_logs(&self) -> Arc<RwLock<LogStore>> {
+        self.sink().logs.clone()
+    }
+
+    fn get_rejects(&self) -> Arc<RwLock<RejectedLogs>> {
+        self.sink().rejects.clone()
+    }
 }
 
+pub mod command;
+pub mod compression;
+pub mod dialect;
 pub mod file_log;
+pub mod line;
+pub mod mapped;
+pub mod multi_file;
+pub mod parallel;
+pub mod parser;
+pub mod rejects;
+pub mod replay;
 pub mod stdin;
+pub mod syslog;
+pub mod tcp;
+pub mod tee;
+pub mod tls;
+pub mod udp;
+
+//Where an ingester puts what it reads: parsed logs within the time window are stored
+//and the packet filter is notified, lines that fail to parse are recorded as rejects.
+//Every raw line is also written to the tee, when set.
+#[derive(Clone)]
+pub struct LogSink {
+    logs: Arc<RwLock<LogStore>>,
+    notify: Arc<Notify>,
+    rejects: Arc<RwLock<RejectedLogs>>,
+    parser: Arc<dyn LogParser>,
+    window: TimeWindow,
+    tee: Option<TeeWriter>,
+}
+
+impl Default for LogSink {
+    fn default() -> Self {
+        Self {
+            logs: Arc::new(RwLock::new(LogStore::default())),
+            notify: Arc::new(Notify::new()),
+            rejects: Arc::new(RwLock::new(RejectedLogs::default())),
+            parser: Arc::new(FilterlogParser::default()),
+            window: TimeWindow::default(),
+            tee: None,
+        }
+    }
+}
+
+impl LogSink {
+    pub fn set_parser(&mut self, parser: Arc<dyn LogParser>) {
+        self.parser = parser;
+    }
+
+    pub fn set_window(&mut self, window: TimeWindow) {
+        self.window = window;
+    }
+
+    pub fn set_tee(&mut self, tee: Option<TeeWriter>) {
+        self.tee = tee;
+    }
+
+    //Limits of the store, set before ingestion starts so that they are never exceeded.
+    pub fn set_limits(&mut self, max_logs: Option<usize>, max_memory: Option<usize>) {
+        if let Ok(mut logs) = self.logs.write() {
+            logs.set_limits(max_logs, max_memory);
+        }
+    }
+
+    pub fn set_rejects_dump(&mut self, dump: Option<File>) {
+        if let (Some(dump), Ok(mut rejects)) = (dump, self.rejects.write()) {
+            rejects.dump_to(dump);
+        }
+    }
+
+    //Parses a raw line, recording why it has been rejected when it fails.
+    //Blank lines are ignored.
+    pub fn parse(&self, raw_log: &str) -> Option<TimestampedLog> {
+        if let Some(tee) = &self.tee {
+            tee.write_line(raw_log);
+        }
+
+        if raw_log.trim().is_empty() {
+            return None;
+        }
+
+        match self.parser.parse(raw_log) {
+            Ok(timestamped_log) => Some(timestamped_log),
+            Err(reason) => {
+                self.reject(raw_log, reason);
+                None
+            }
+        }
+    }
+
+    pub fn reject(&self, raw_log: &str, reason: ParseError) {
+        if let Ok(mut lock_guard) = self.rejects.write() {
+            lock_guard.push(raw_log, reason);
+        }
+    }
+
+    pub fn push(&self, timestamped_log: TimestampedLog) {
+        if !self.window.contains(&timestamped_log.timestamp) {
+            return;
+        }
+
+        if let Ok(mut lock_guard) = self.logs.write() {
+            lock_guard.push(timestamped_log);
+        }
+
+        //notify packet filter
+        self.notify.notify_one();
+    }
+
+    //Stores a batch of logs under a single lock, the packet filter is notified once.
+    pub fn push_batch<I: IntoIterator<Item = TimestampedLog>>(&self, timestamped_logs: I) {
+        if let Ok(mut lock_guard) = self.logs.write() {
+            for timestamped_log in timestamped_logs {
+                if self.window.contains(&timestamped_log.timestamp) {
+                    lock_guard.push(timestamped_log);
+                }
+            }
+        }
+
+        self.notify.notify_one();
+    }
+
+    pub fn store(&self, raw_log: &str) {
+        if let Some(timestamped_log) = self.parse(raw_log) {
+            self.push(timestamped_log);
+        }
+    }
+
+    //Stores every line of a source until it is exhausted.
+    pub async fn ingest<S: LineSource>(&self, source: &mut S) -> io::Result<()> {
+        while let Some(raw_log) = source.next_line().await? {
+            self.store(&raw_log);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct ParseListenAddrError;
+
+impl fmt::Display for ParseListenAddrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid listen address, expected udp://, tcp:// or tls:// and ip:port"
+        )
+    }
+}
+
+//Address of a network syslog listener, e.g. udp://0.0.0.0:5514, tcp://0.0.0.0:1514
+//or tls://0.0.0.0:6514
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ListenAddr {
+    Udp(SocketAddr),
+    Tcp(SocketAddr),
+    Tls(SocketAddr),
+}
+
+impl FromStr for ListenAddr {
+    type Err = ParseListenAddrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (scheme, addr) = s.split_once("://").ok_or(ParseListenAddrError)?;
+        let addr = SocketAddr::from_str(addr).map_err(|_| ParseListenAddrError)?;
+
+        match scheme.to_lowercase().as_str() {
+            "udp" => Ok(ListenAddr::Udp(addr)),
+            "tcp" => Ok(ListenAddr::Tcp(addr)),
+            "tls" => Ok(ListenAddr::Tls(addr)),
+            _ => Err(ParseListenAddrError),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::{
+        fs,
+        ops::Deref,
+        path::{Path, PathBuf},
+    };
+
+    pub const SYSLOG_LINE: &str = "<134>1 2025-01-02T12:00:00+01:00 fw1.local filterlog 12345 - \
+        [meta sequenceId=\"1\"] 96,,,fae559338f65e11c53669fc3642c93c2,vlan0.20,match,pass,out,\
+        4,0x0,,127,61633,0,DF,6,tcp,52,192.168.10.15,192.168.20.14,\
+        52461,9100,0,S,3442468761,,64240,,mss;nop;wscale;nop;nop;sackOK";
+
+    pub fn syslog_line_at(timestamp: &str) -> String {
+        SYSLOG_LINE.replace("2025-01-02T12:00:00+01:00", timestamp)
+    }
+
+    //Directory of a test, removed with its content when dropped, even if the test fails.
+    pub struct TempDir(PathBuf);
+
+    impl TempDir {
+        pub fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("firetail-{}-{}", name, std::process::id()));
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Deref for TempDir {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_parse_listen_addr() {
+        assert_eq!(
+            ListenAddr::from_str("udp://0.0.0.0:5514"),
+            Ok(ListenAddr::Udp(
+                SocketAddr::from_str("0.0.0.0:5514").unwrap()
+            ))
+        );
+        assert_eq!(
+            ListenAddr::from_str("UDP://[::1]:514"),
+            Ok(ListenAddr::Udp(SocketAddr::from_str("[::1]:514").unwrap()))
+        );
+        assert_eq!(
+            ListenAddr::from_str("tcp://127.0.0.1:1514"),
+            Ok(ListenAddr::Tcp(
+                SocketAddr::from_str("127.0.0.1:1514").unwrap()
+            ))
+        );
+        assert_eq!(
+            ListenAddr::from_str("tls://0.0.0.0:6514"),
+            Ok(ListenAddr::Tls(
+                SocketAddr::from_str("0.0.0.0:6514").unwrap()
+            ))
+        );
+        assert_eq!(
+            ListenAddr::from_str("0.0.0.0:5514"),
+            Err(ParseListenAddrError)
+        );
+        assert_eq!(
+            ListenAddr::from_str("udp://0.0.0.0"),
+            Err(ParseListenAddrError)
+        );
+        assert_eq!(
+            ListenAddr::from_str("sctp://0.0.0.0:5514"),
+            Err(ParseListenAddrError)
+        );
+    }
+}
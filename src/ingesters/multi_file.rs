@@ -0,0 +1,129 @@
+use std::{io, path::PathBuf};
+
+use crate::TimestampedLog;
+
+use super::{file_log::open_lines, line::LineSource, LogIngester, LogSink};
+
+//Reads several log files as a single timeline.
+//Files are read side by side and merged by timestamp, so the store stays sorted
+//as long as every file is sorted, which is the case for firewall logs. A file
+//that goes back in time is not reordered, its logs are merged as they come.
+pub struct MultiFileLogIngester {
+    sink: LogSink,
+    paths: Vec<PathBuf>,
+}
+
+impl MultiFileLogIngester {
+    pub fn new(paths: Vec<PathBuf>) -> io::Result<Self> {
+        if let Some(path) = paths.iter().find(|path| !path.exists()) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("the file {} doesn't exist", path.display()),
+            ));
+        }
+
+        Ok(Self {
+            sink: LogSink::default(),
+            paths,
+        })
+    }
+}
+
+//Returns the next parsable log of a source, recording the lines that are rejected.
+async fn next_log<S: LineSource>(
+    lines: &mut S,
+    sink: &LogSink,
+) -> io::Result<Option<TimestampedLog>> {
+    while let Some(raw_log) = lines.next_line().await? {
+        if let Some(timestamped_log) = sink.parse(&raw_log) {
+            return Ok(Some(timestamped_log));
+        }
+    }
+    Ok(None)
+}
+
+impl LogIngester for MultiFileLogIngester {
+    async fn ingest(&mut self) -> io::Result<()> {
+        let mut sources = Vec::with_capacity(self.paths.len());
+        for path in &self.paths {
+            let mut lines = open_lines(path).await?;
+            let head = next_log(&mut lines, &self.sink).await?;
+            sources.push((lines, head));
+        }
+
+        loop {
+            //Pick the oldest pending log among all files, the first file wins ties.
+            let oldest = sources
+                .iter()
+                .enumerate()
+                .filter_map(|(index, (_, head))| Some((index, head.as_ref()?.timestamp)))
+                .min_by_key(|(_, timestamp)| *timestamp)
+                .map(|(index, _)| index);
+
+            let Some(index) = oldest else {
+                return Ok(());
+            };
+
+            let (lines, head) = &mut sources[index];
+            let next = next_log(lines, &self.sink).await?;
+            let timestamped_log = std::mem::replace(head, next).unwrap();
+
+            //The merged timeline is sorted, nothing left can be in the window.
+            if self.sink.window.is_past(&timestamped_log.timestamp) {
+                return Ok(());
+            }
+
+            self.sink.push(timestamped_log);
+        }
+    }
+
+    fn sink(&self) -> &LogSink {
+        &self.sink
+    }
+
+    fn sink_mut(&mut self) -> &mut LogSink {
+        &mut self.sink
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingesters::tests::{syslog_line_at, TempDir};
+
+    #[tokio::test]
+    async fn test_merge_by_timestamp() {
+        let dir = TempDir::new("merge");
+
+        let first = [
+            "2025-01-01T10:00:00+00:00",
+            "2025-01-01T12:00:00+00:00",
+            "2025-01-02T09:00:00+00:00",
+        ];
+        let second = ["2025-01-01T11:00:00+00:00", "2025-01-02T08:00:00+01:00"];
+        for (name, timestamps) in [("first.log", &first[..]), ("second.log", &second[..])] {
+            let content: String = timestamps
+                .iter()
+                .map(|timestamp| syslog_line_at(timestamp) + "\n")
+                .collect();
+            std::fs::write(dir.join(name), content + "garbage\n").unwrap();
+        }
+
+        let mut ingester =
+            MultiFileLogIngester::new(vec![dir.join("first.log"), dir.join("second.log")]).unwrap();
+        ingester.ingest().await.unwrap();
+
+        let logs = ingester.get_logs();
+        let logs = logs.read().unwrap();
+        let logs: Vec<_> = logs.iter().collect();
+        assert_eq!(logs.len(), 5);
+        assert_eq!(ingester.get_rejects().read().unwrap().total(), 2);
+        assert!(logs.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let err = MultiFileLogIngester::new(vec!["/nonexistent/filter.log".into()]).err();
+        assert_eq!(err.map(|e| e.kind()), Some(io::ErrorKind::NotFound));
+    }
+}
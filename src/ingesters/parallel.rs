@@ -0,0 +1,237 @@
+use std::{
+    collections::VecDeque,
+    io,
+    path::{Path, PathBuf},
+    thread,
+};
+
+use tokio::{io::AsyncReadExt, task};
+
+use crate::TimestampedLog;
+
+use super::{
+    file_log::open_reader,
+    parser::{LogParser, ParseError},
+    LogIngester, LogSink,
+};
+
+//Bytes read before a block is handed to a worker.
+const BLOCK_SIZE: usize = 1 << 20;
+
+type ParsedLine = Result<TimestampedLog, (String, ParseError)>;
+
+//Reads a whole log file, parsing line aligned blocks on several workers.
+//Blocks are committed to the store in file order, one notification per block.
+//The file is expected to be sorted: reading stops past the end of the time window.
+pub struct ParallelFileIngester {
+    sink: LogSink,
+    path: PathBuf,
+    workers: usize,
+    block_size: usize,
+}
+
+impl ParallelFileIngester {
+    pub fn new<T: AsRef<Path>>(path: T) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if !path.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("the file {} doesn't exist", path.display()),
+            ));
+        }
+
+        Ok(Self {
+            sink: LogSink::default(),
+            path,
+            workers: thread::available_parallelism().map_or(1, |n| n.get()),
+            block_size: BLOCK_SIZE,
+        })
+    }
+
+    //Number of blocks parsed at the same time.
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    pub fn block_size(mut self, block_size: usize) -> Self {
+        self.block_size = block_size.max(1);
+        self
+    }
+
+    //Returns whether the block goes past the end of the time window.
+    fn commit(&self, lines: Vec<ParsedLine>) -> bool {
+        let past = lines
+            .iter()
+            .rev()
+            .find_map(|line| line.as_ref().ok())
+            .is_some_and(|timestamped_log| self.sink.window.is_past(&timestamped_log.timestamp));
+        let mut logs = Vec::with_capacity(lines.len());
+        for line in lines {
+            match line {
+                Ok(timestamped_log) => logs.push(timestamped_log),
+                Err((raw_log, reason)) => self.sink.reject(&raw_log, reason),
+            }
+        }
+        self.sink.push_batch(logs);
+        past
+    }
+}
+
+impl LogIngester for ParallelFileIngester {
+    async fn ingest(&mut self) -> io::Result<()> {
+        let mut reader = open_reader(&self.path).await?;
+        let mut pending = VecDeque::new();
+        //Start of a line not terminated by the previous block.
+        let mut partial = Vec::new();
+
+        loop {
+            let mut block = std::mem::take(&mut partial);
+            let read = (&mut reader)
+                .take(self.block_size as u64)
+                .read_to_end(&mut block)
+                .await?;
+            let eof = read == 0;
+
+            if !eof {
+                match block.iter().rposition(|&b| b == b'\n') {
+                    Some(newline) => partial = block.split_off(newline + 1),
+                    //The line is longer than a block, keep reading it.
+                    None => {
+                        partial = block;
+                        continue;
+                    }
+                }
+            }
+
+            if !block.is_empty() {
+                let parser = self.sink.parser.clone();
+                pending.push_back(task::spawn_blocking(move || parse_block(&block, &*parser)));
+            }
+
+            while pending.len() >= self.workers || (eof && !pending.is_empty()) {
+                let lines = pending
+                    .pop_front()
+                    .unwrap()
+                    .await
+                    .map_err(io::Error::other)?;
+                if self.commit(lines) {
+                    return Ok(());
+                }
+            }
+
+            if eof {
+                return Ok(());
+            }
+        }
+    }
+
+    fn sink(&self) -> &LogSink {
+        &self.sink
+    }
+
+    fn sink_mut(&mut self) -> &mut LogSink {
+        &mut self.sink
+    }
+}
+
+//Parses every line of a block, blank lines are skipped.
+fn parse_block(block: &[u8], parser: &dyn LogParser) -> Vec<ParsedLine> {
+    block
+        .split(|&b| b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .map(String::from_utf8_lossy)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            parser
+                .parse(&line)
+                .map_err(|reason| (line.into_owned(), reason))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ingesters::{
+            file_log::{FileLogIngester, FileSource},
+            tests::{syslog_line_at, TempDir},
+        },
+        store::LogStore,
+        timestamp::TimeWindow,
+    };
+    use chrono::{DateTime, Duration, Local};
+    use std::sync::RwLock;
+
+    fn timestamps(logs: &RwLock<LogStore>) -> Vec<DateTime<Local>> {
+        logs.read()
+            .unwrap()
+            .iter()
+            .map(|log| log.timestamp)
+            .collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_same_logs_as_file_ingester() {
+        let start = DateTime::parse_from_rfc3339("2025-01-02T10:00:00Z").unwrap();
+        let mut content = String::new();
+        for minute in 0..200 {
+            let timestamp = (start + Duration::minutes(minute)).to_rfc3339();
+            content.push_str(&syslog_line_at(&timestamp));
+            content.push_str(if minute % 50 == 0 {
+                "\ngarbage\r\n"
+            } else {
+                "\n"
+            });
+        }
+        //No final newline.
+        content.push_str(&syslog_line_at("2025-01-02T14:00:00Z"));
+
+        let dir = TempDir::new("parallel");
+        let path = dir.join("filter.log");
+        std::fs::write(&path, content).unwrap();
+
+        let mut parallel = ParallelFileIngester::new(&path)
+            .unwrap()
+            .workers(3)
+            .block_size(1000);
+        parallel.ingest().await.unwrap();
+        let mut sequential = FileLogIngester::new(FileSource::new(&path).await.unwrap());
+        sequential.ingest().await.unwrap();
+
+        let expected = timestamps(&sequential.get_logs());
+        assert_eq!(expected.len(), 201);
+        assert_eq!(timestamps(&parallel.get_logs()), expected);
+        assert_eq!(parallel.get_rejects().read().unwrap().total(), 4);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_stop_past_window() {
+        let at =
+            |hour| DateTime::parse_from_rfc3339(&format!("2025-01-02T{}:00:00Z", hour)).unwrap();
+        let mut content: String = (10..15)
+            .map(|hour| format!("{}\n", syslog_line_at(&at(hour).to_rfc3339())))
+            .collect();
+        content.push_str("garbage\n");
+
+        let dir = TempDir::new("window");
+        let path = dir.join("filter.log");
+        std::fs::write(&path, content).unwrap();
+
+        let window = TimeWindow {
+            since: Some(at(11).with_timezone(&Local)),
+            until: Some(at(12).with_timezone(&Local)),
+        };
+        let mut ingester = ParallelFileIngester::new(&path)
+            .unwrap()
+            .workers(1)
+            .block_size(1);
+        ingester.sink_mut().set_window(window);
+        ingester.ingest().await.unwrap();
+
+        assert_eq!(timestamps(&ingester.get_logs()), [at(11), at(12)]);
+        //The garbage line after the window has not been read.
+        assert_eq!(ingester.get_rejects().read().unwrap().total(), 0);
+    }
+}
@@ -0,0 +1,215 @@
+use std::{error::Error, fmt::Display};
+
+use chrono::{DateTime, Local};
+
+use crate::{
+    timestamp::{parse_timestamp, TimestampSource},
+    TimestampedLog,
+};
+
+use super::{
+    dialect::{reason, Dialect},
+    syslog::{parse_rfc3164, parse_rfc5424, SyslogMessage},
+};
+
+//Why a raw line could not be turned into a log.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ParseError {
+    SyslogHeader,
+    Filterlog(String),
+    Timestamp(String),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::SyslogHeader => write!(f, "invalid syslog header"),
+            ParseError::Filterlog(reason) => write!(f, "filterlog: {}", reason),
+            ParseError::Timestamp(timestamp) => write!(f, "bad timestamp: {}", timestamp),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+//Turns a raw line read by an ingester into a log, independently of where the line comes from.
+pub trait LogParser: Send + Sync {
+    fn parse(&self, raw_log: &str) -> Result<TimestampedLog, ParseError>;
+
+    //Timestamp of a line, used to index files whose lines are parsed on demand.
+    //Parsers able to find it without parsing the whole line should override it.
+    fn timestamp(&self, raw_log: &str) -> Result<DateTime<Local>, ParseError> {
+        self.parse(raw_log).map(|log| log.timestamp)
+    }
+}
+
+//Parses lines carrying a filterlog message. The shape of the line is detected on
+//each line: RFC 5424 or RFC 3164 syslog, or the bare filterlog CSV payload, which
+//always starts with the rule number.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FilterlogParser {
+    dialect: Dialect,
+}
+
+impl FilterlogParser {
+    pub fn new(dialect: Dialect) -> Self {
+        Self { dialect }
+    }
+}
+
+impl LogParser for FilterlogParser {
+    fn parse(&self, raw_log: &str) -> Result<TimestampedLog, ParseError> {
+        let msg = syslog_message(raw_log)?;
+        let flog = self
+            .dialect
+            .parse(msg.msg)
+            .map_err(|e| ParseError::Filterlog(e.reason))?;
+        let (timestamp, timestamp_source) = message_timestamp(&msg)?;
+
+        Ok(TimestampedLog {
+            log: flog,
+            timestamp,
+            timestamp_source,
+            header: msg.header.into_owned(),
+            reason: reason(msg.msg).into(),
+        })
+    }
+
+    //Only the header is parsed, a payload not starting with the rule number is rejected
+    //early so that other messages of a shared syslog file are not indexed.
+    fn timestamp(&self, raw_log: &str) -> Result<DateTime<Local>, ParseError> {
+        let msg = syslog_message(raw_log)?;
+        if !msg.msg.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(ParseError::Filterlog("missing rule number".into()));
+        }
+        message_timestamp(&msg).map(|(timestamp, _)| timestamp)
+    }
+}
+
+fn syslog_message(raw_log: &str) -> Result<SyslogMessage<'_>, ParseError> {
+    parse_rfc5424(raw_log)
+        .or_else(|| parse_rfc3164(raw_log))
+        .or_else(|| {
+            raw_log
+                .starts_with(|c: char| c.is_ascii_digit())
+                .then_some(SyslogMessage {
+                    msg: raw_log,
+                    ..Default::default()
+                })
+        })
+        .ok_or(ParseError::SyslogHeader)
+}
+
+fn message_timestamp(
+    msg: &SyslogMessage<'_>,
+) -> Result<(DateTime<Local>, TimestampSource), ParseError> {
+    let now = Local::now();
+    match msg.timestamp {
+        Some(timestamp) => {
+            parse_timestamp(timestamp, now).ok_or_else(|| ParseError::Timestamp(timestamp.into()))
+        }
+        None => Ok((now, TimestampSource::Received)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingesters::tests::{syslog_line_at, SYSLOG_LINE};
+
+    #[test]
+    fn test_parse_syslog_line() {
+        let parser = FilterlogParser::default();
+
+        let log = parser.parse(SYSLOG_LINE).unwrap();
+        assert_eq!(log.log.packet_filter.interface, "vlan0.20");
+        assert_eq!(
+            log.timestamp,
+            chrono::DateTime::parse_from_rfc3339("2025-01-02T12:00:00+01:00").unwrap()
+        );
+        assert_eq!(
+            log.timestamp_source,
+            TimestampSource::Rfc5424(chrono::FixedOffset::east_opt(3600))
+        );
+
+        let log = parser.parse(&syslog_line_at("-")).unwrap();
+        assert_eq!(log.timestamp_source, TimestampSource::Received);
+    }
+
+    #[test]
+    fn test_detect_line_shape() {
+        let parser = FilterlogParser::default();
+        let (_, payload) = SYSLOG_LINE.split_once("] ").unwrap();
+
+        let log = parser
+            .parse(&format!(
+                "<134>Jan  2 12:00:00 fw1 filterlog[123]: {}",
+                payload
+            ))
+            .unwrap();
+        assert_eq!(log.timestamp_source, TimestampSource::Rfc3164);
+        assert_eq!(
+            log.timestamp.format("%m-%d %H:%M:%S").to_string(),
+            "01-02 12:00:00"
+        );
+
+        let log = parser.parse(payload).unwrap();
+        assert_eq!(log.timestamp_source, TimestampSource::Received);
+        assert_eq!(log.log.packet_filter.interface, "vlan0.20");
+        assert_eq!(
+            parser.parse("not a syslog line").err(),
+            Some(ParseError::SyslogHeader)
+        );
+        assert_eq!(
+            parser
+                .parse(&SYSLOG_LINE.replace("vlan0.20,match,pass", "vlan0.20,match,drop"))
+                .err(),
+            Some(ParseError::Filterlog(
+                "Failed to parse packet filter".into()
+            ))
+        );
+        assert_eq!(
+            parser.parse(&syslog_line_at("yesterday")).err(),
+            Some(ParseError::Timestamp("yesterday".into()))
+        );
+    }
+
+    #[test]
+    fn test_parse_pfsense_line() {
+        let line =
+            "<134>Jan  2 12:00:00 pfsense filterlog[123]: 5,,,1000000103,igb1,match,block,in,\
+            4,0x0,,64,0,0,none,112,carp,56,10.0.0.2,224.0.0.18,advertise,1,0,1,20";
+
+        let log = FilterlogParser::new(Dialect::Pfsense).parse(line).unwrap();
+        assert_eq!(log.log.packet_filter.interface, "igb1");
+        assert_eq!(log.reason, "match");
+
+        let log = FilterlogParser::new(Dialect::Pfsense)
+            .parse(&line.replace(",match,", ",state-mismatch,"))
+            .unwrap();
+        assert_eq!(log.reason, "state-mismatch");
+        assert!(FilterlogParser::new(Dialect::Auto).parse(line).is_ok());
+        assert!(matches!(
+            FilterlogParser::new(Dialect::Opnsense).parse(line),
+            Err(ParseError::Filterlog(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_timestamp_only() {
+        let parser = FilterlogParser::default();
+
+        assert_eq!(
+            parser.timestamp(SYSLOG_LINE).unwrap(),
+            parser.parse(SYSLOG_LINE).unwrap().timestamp
+        );
+        assert!(matches!(
+            parser.timestamp("<13>1 2025-01-02T12:00:00Z fw1 sshd 1 - - login"),
+            Err(ParseError::Filterlog(_))
+        ));
+        assert!(matches!(
+            parser.timestamp(&syslog_line_at("yesterday")),
+            Err(ParseError::Timestamp(_))
+        ));
+    }
+}
@@ -0,0 +1,138 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use chrono::{DateTime, Local};
+
+use super::parser::ParseError;
+
+//Number of rejected lines kept in memory, older ones are only counted.
+const DEFAULT_CAPACITY: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct RejectedLog {
+    pub received: DateTime<Local>,
+    pub reason: ParseError,
+    pub raw_log: String,
+}
+
+//Bounded store of the lines that failed to parse.
+#[derive(Debug)]
+pub struct RejectedLogs {
+    entries: VecDeque<RejectedLog>,
+    capacity: usize,
+    pub syslog_header: usize,
+    pub filterlog: usize,
+    pub timestamp: usize,
+    //Every rejected line is appended here as well, when set.
+    dump: Option<BufWriter<File>>,
+}
+
+impl Default for RejectedLogs {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl RejectedLogs {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            syslog_header: 0,
+            filterlog: 0,
+            timestamp: 0,
+            dump: None,
+        }
+    }
+
+    //Appends every line rejected from now on to `dump`, set before ingestion starts
+    //so that the lines evicted from memory are dumped as well.
+    pub fn dump_to(&mut self, dump: File) {
+        self.dump = Some(BufWriter::new(dump));
+    }
+
+    pub fn push(&mut self, raw_log: &str, reason: ParseError) {
+        match reason {
+            ParseError::SyslogHeader => self.syslog_header += 1,
+            ParseError::Filterlog(_) => self.filterlog += 1,
+            ParseError::Timestamp(_) => self.timestamp += 1,
+        }
+
+        if let Some(dump) = &mut self.dump {
+            //A failing dump must not stop ingestion. Each line is written at once.
+            if writeln!(dump, "{}\t{}", reason, raw_log)
+                .and_then(|_| dump.flush())
+                .is_err()
+            {
+                self.dump = None;
+            }
+        }
+
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(RejectedLog {
+            received: Local::now(),
+            reason,
+            raw_log: raw_log.to_string(),
+        });
+    }
+
+    pub fn total(&self) -> usize {
+        self.syslog_header + self.filterlog + self.timestamp
+    }
+
+    //Most recent rejected lines, oldest first.
+    pub fn entries(&self) -> &VecDeque<RejectedLog> {
+        &self.entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingesters::tests::TempDir;
+
+    #[test]
+    fn test_bounded() {
+        let mut rejects = RejectedLogs::new(2);
+        rejects.push("first", ParseError::SyslogHeader);
+        rejects.push(
+            "second",
+            ParseError::Filterlog("Failed to parse IP header".into()),
+        );
+        rejects.push("third", ParseError::Timestamp("-".into()));
+
+        assert_eq!(rejects.total(), 3);
+        assert_eq!(rejects.syslog_header, 1);
+        assert_eq!(rejects.filterlog, 1);
+        assert_eq!(rejects.timestamp, 1);
+
+        let raw_logs: Vec<_> = rejects
+            .entries()
+            .iter()
+            .map(|e| e.raw_log.as_str())
+            .collect();
+        assert_eq!(raw_logs, ["second", "third"]);
+    }
+
+    #[test]
+    fn test_dump() {
+        let dir = TempDir::new("rejects");
+        let path = dir.join("rejects.log");
+        let mut rejects = RejectedLogs::new(1);
+        rejects.dump_to(File::create(&path).unwrap());
+        rejects.push("first", ParseError::SyslogHeader);
+        rejects.push("second", ParseError::Timestamp("-".into()));
+
+        //Lines no longer kept in memory have been dumped.
+        assert_eq!(rejects.entries().len(), 1);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "invalid syslog header\tfirst\nbad timestamp: -\tsecond\n"
+        );
+    }
+}
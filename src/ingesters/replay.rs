@@ -0,0 +1,222 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use chrono::{DateTime, Local};
+use tokio::{
+    io,
+    sync::Notify,
+    time::{self, Instant},
+};
+
+use super::{line::LineSource, LogIngester, LogSink};
+
+//State of a replay, shown in the ui.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReplayStatus {
+    pub speed: f64,
+    pub paused: bool,
+    pub replayed: usize,
+    pub done: bool,
+    //Logs released while paused.
+    steps: usize,
+}
+
+//Lets the ui pause, resume and step a replay.
+#[derive(Debug)]
+pub struct ReplayControl {
+    status: RwLock<ReplayStatus>,
+    changed: Notify,
+}
+
+impl ReplayControl {
+    pub fn status(&self) -> ReplayStatus {
+        self.status.read().unwrap().clone()
+    }
+
+    pub fn toggle_pause(&self) {
+        if let Ok(mut status) = self.status.write() {
+            status.paused = !status.paused;
+        }
+        self.changed.notify_one();
+    }
+
+    //Pauses the replay and releases the next log.
+    pub fn step(&self) {
+        if let Ok(mut status) = self.status.write() {
+            status.paused = true;
+            status.steps += 1;
+        }
+        self.changed.notify_one();
+    }
+
+    //Waits `delay` while the replay is not paused, or for a step.
+    async fn wait(&self, delay: Duration) {
+        let mut remaining = delay;
+        loop {
+            let changed = self.changed.notified();
+            let paused = {
+                let mut status = self.status.write().unwrap();
+                if status.steps > 0 {
+                    status.steps -= 1;
+                    return;
+                }
+                if !status.paused && remaining.is_zero() {
+                    return;
+                }
+                status.paused
+            };
+
+            if paused {
+                changed.await;
+                continue;
+            }
+
+            let start = Instant::now();
+            tokio::select! {
+                _ = time::sleep(remaining) => remaining = Duration::ZERO,
+                _ = changed => remaining = remaining.saturating_sub(start.elapsed()),
+            }
+        }
+    }
+
+    fn replayed(&self) {
+        if let Ok(mut status) = self.status.write() {
+            status.replayed += 1;
+        }
+    }
+
+    fn done(&self) {
+        if let Ok(mut status) = self.status.write() {
+            status.done = true;
+        }
+    }
+}
+
+//Stores the logs of a recorded source with the time elapsed between their timestamps,
+//divided by the speed. Lines that fail to parse are rejected right away.
+pub struct ReplayIngester<S> {
+    source: S,
+    sink: LogSink,
+    control: Arc<ReplayControl>,
+}
+
+impl<S: LineSource> ReplayIngester<S> {
+    pub fn new(source: S, speed: f64) -> Self {
+        Self {
+            source,
+            sink: LogSink::default(),
+            control: Arc::new(ReplayControl {
+                status: RwLock::new(ReplayStatus {
+                    speed,
+                    ..Default::default()
+                }),
+                changed: Notify::new(),
+            }),
+        }
+    }
+
+    pub fn get_control(&self) -> Arc<ReplayControl> {
+        self.control.clone()
+    }
+
+    fn delay(&self, previous: Option<DateTime<Local>>, timestamp: DateTime<Local>) -> Duration {
+        let Some(previous) = previous else {
+            return Duration::ZERO;
+        };
+        //Out of order logs are replayed right away.
+        let elapsed = (timestamp - previous).to_std().unwrap_or_default();
+        let speed = self.control.status.read().unwrap().speed;
+        Duration::try_from_secs_f64(elapsed.as_secs_f64() / speed).unwrap_or(Duration::MAX)
+    }
+}
+
+impl<S: LineSource> LogIngester for ReplayIngester<S> {
+    async fn ingest(&mut self) -> io::Result<()> {
+        let mut previous = None;
+        while let Some(raw_log) = self.source.next_line().await? {
+            let Some(timestamped_log) = self.sink.parse(&raw_log) else {
+                continue;
+            };
+            //Recordings are expected to be sorted.
+            if self.sink.window.is_past(&timestamped_log.timestamp) {
+                break;
+            }
+            if !self.sink.window.contains(&timestamped_log.timestamp) {
+                continue;
+            }
+
+            let timestamp = timestamped_log.timestamp;
+            self.control.wait(self.delay(previous, timestamp)).await;
+            previous = Some(timestamp);
+            self.sink.push(timestamped_log);
+            self.control.replayed();
+        }
+
+        self.control.done();
+        Ok(())
+    }
+
+    fn sink(&self) -> &LogSink {
+        &self.sink
+    }
+
+    fn sink_mut(&mut self) -> &mut LogSink {
+        &mut self.sink
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingesters::tests::syslog_line_at;
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    fn recording() -> Vec<u8> {
+        ["10:00:00", "10:00:10", "10:00:30"]
+            .iter()
+            .map(|time| format!("{}\n", syslog_line_at(&format!("2025-01-02T{}Z", time))))
+            .collect::<String>()
+            .into_bytes()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_replay_timing() {
+        let source = BufReader::new(std::io::Cursor::new(recording())).lines();
+        let mut ingester = ReplayIngester::new(source, 10.0);
+        let start = Instant::now();
+        ingester.ingest().await.unwrap();
+
+        assert_eq!(start.elapsed(), Duration::from_secs(3));
+        assert_eq!(ingester.get_logs().read().unwrap().len(), 3);
+        let status = ingester.get_control().status();
+        assert!(status.done);
+        assert_eq!(status.replayed, 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_pause_and_step() {
+        let source = BufReader::new(std::io::Cursor::new(recording())).lines();
+        let mut ingester = ReplayIngester::new(source, 1.0);
+        let control = ingester.get_control();
+        let logs = ingester.get_logs();
+        control.toggle_pause();
+        let handle = tokio::spawn(async move { ingester.ingest().await });
+
+        time::sleep(Duration::from_secs(100)).await;
+        assert_eq!(logs.read().unwrap().len(), 0);
+
+        control.step();
+        control.step();
+        time::sleep(Duration::from_secs(100)).await;
+        assert_eq!(logs.read().unwrap().len(), 2);
+        assert!(control.status().paused);
+
+        control.toggle_pause();
+        let start = Instant::now();
+        handle.await.unwrap().unwrap();
+        assert_eq!(start.elapsed(), Duration::from_secs(20));
+        assert_eq!(logs.read().unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,236 @@
+use rsyslog::Message;
+
+const FACILITIES: [&str; 24] = [
+    "kern",
+    "user",
+    "mail",
+    "daemon",
+    "auth",
+    "syslog",
+    "lpr",
+    "news",
+    "uucp",
+    "cron",
+    "authpriv",
+    "ftp",
+    "ntp",
+    "security",
+    "console",
+    "solaris-cron",
+    "local0",
+    "local1",
+    "local2",
+    "local3",
+    "local4",
+    "local5",
+    "local6",
+    "local7",
+];
+
+const SEVERITIES: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+//The parts of a syslog message the ingesters need, whatever the header format.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub(crate) struct SyslogMessage<'a> {
+    pub timestamp: Option<&'a str>,
+    pub header: SyslogHeader<&'a str>,
+    pub msg: &'a str,
+}
+
+//Who sent a message, the fields missing from the line are None.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct SyslogHeader<S = String> {
+    pub hostname: Option<S>,
+    pub app_name: Option<S>,
+    pub proc_id: Option<S>,
+    pub facility: Option<u8>,
+    pub severity: Option<u8>,
+}
+
+impl SyslogHeader<&str> {
+    pub fn into_owned(self) -> SyslogHeader {
+        SyslogHeader {
+            hostname: self.hostname.map(String::from),
+            app_name: self.app_name.map(String::from),
+            proc_id: self.proc_id.map(String::from),
+            facility: self.facility,
+            severity: self.severity,
+        }
+    }
+}
+
+impl SyslogHeader {
+    pub fn facility_name(&self) -> Option<&'static str> {
+        FACILITIES.get(usize::from(self.facility?)).copied()
+    }
+
+    pub fn severity_name(&self) -> Option<&'static str> {
+        SEVERITIES.get(usize::from(self.severity?)).copied()
+    }
+}
+
+//`<134>1 2025-01-02T12:00:00+01:00 fw1 filterlog 123 - - ...`
+pub(crate) fn parse_rfc5424(line: &str) -> Option<SyslogMessage<'_>> {
+    let msg: Message = Message::parse(line).ok()?;
+
+    Some(SyslogMessage {
+        timestamp: msg.timestamp,
+        header: SyslogHeader {
+            hostname: msg.hostname,
+            app_name: msg.app_name,
+            proc_id: msg.proc_id,
+            facility: Some(msg.facility),
+            severity: Some(msg.severity),
+        },
+        msg: msg.msg.msg,
+    })
+}
+
+//`<134>Jan  2 12:00:00 fw1 filterlog[123]: ...`, as written by traditional syslog daemons.
+//The priority and the hostname are optional, relays that store an RFC 3339 timestamp
+//in place of the traditional one are supported too.
+pub(crate) fn parse_rfc3164(line: &str) -> Option<SyslogMessage<'_>> {
+    let mut header = SyslogHeader::default();
+    let rest = match line.strip_prefix('<') {
+        Some(rest) => {
+            let (pri, rest) = rest.split_once('>')?;
+            if pri.is_empty() || pri.len() > 3 || !pri.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            let pri: u8 = pri.parse().ok()?;
+            header.facility = Some(pri >> 3);
+            header.severity = Some(pri & 7);
+            rest
+        }
+        None => line,
+    };
+
+    let (timestamp, rest) = if rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        //Mmm dd hh:mm:ss, the day is padded with a space.
+        let (month, after_month) = next_token(rest)?;
+        let (day, after_day) = next_token(after_month)?;
+        let (time, after_time) = next_token(after_day)?;
+        if month.len() != 3 || !day.bytes().all(|b| b.is_ascii_digit()) || time.len() != 8 {
+            return None;
+        }
+        (rest[..rest.len() - after_time.len()].trim_end(), after_time)
+    } else {
+        next_token(rest)?
+    };
+
+    //The tag, e.g. `filterlog[123]:`, may directly follow the timestamp.
+    let (mut tag, mut rest) = next_token(rest)?;
+    if !tag.ends_with(':') {
+        header.hostname = Some(tag);
+        (tag, rest) = next_token(rest)?;
+        if !tag.ends_with(':') {
+            return None;
+        }
+    }
+
+    let tag = tag.trim_end_matches(':');
+    match tag.strip_suffix(']').and_then(|tag| tag.split_once('[')) {
+        Some((app_name, proc_id)) => {
+            header.app_name = Some(app_name);
+            header.proc_id = Some(proc_id);
+        }
+        None => header.app_name = Some(tag).filter(|tag| !tag.is_empty()),
+    }
+
+    Some(SyslogMessage {
+        timestamp: Some(timestamp),
+        header,
+        msg: rest.trim_start(),
+    })
+}
+
+//Splits the first space separated token off `s`.
+fn next_token(s: &str) -> Option<(&str, &str)> {
+    let s = s.trim_start_matches(' ');
+    if s.is_empty() {
+        return None;
+    }
+    Some(s.split_once(' ').unwrap_or((s, "")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message<'a>(
+        timestamp: &'a str,
+        hostname: Option<&'a str>,
+        proc_id: Option<&'a str>,
+        facility: Option<u8>,
+    ) -> SyslogMessage<'a> {
+        SyslogMessage {
+            timestamp: Some(timestamp),
+            header: SyslogHeader {
+                hostname,
+                app_name: Some("filterlog"),
+                proc_id,
+                facility,
+                severity: facility.map(|_| 6),
+            },
+            msg: "96,,,abc",
+        }
+    }
+
+    #[test]
+    fn test_rfc3164() {
+        assert_eq!(
+            parse_rfc3164("<134>Jan  2 12:00:00 fw1 filterlog[123]: 96,,,abc"),
+            Some(message(
+                "Jan  2 12:00:00",
+                Some("fw1"),
+                Some("123"),
+                Some(16)
+            ))
+        );
+        assert_eq!(
+            parse_rfc3164("Jan 12 12:00:00 fw1 filterlog: 96,,,abc"),
+            Some(message("Jan 12 12:00:00", Some("fw1"), None, None))
+        );
+        assert_eq!(
+            parse_rfc3164("<134>Jan  2 12:00:00 filterlog[123]: 96,,,abc"),
+            Some(message("Jan  2 12:00:00", None, Some("123"), Some(16)))
+        );
+        assert_eq!(
+            parse_rfc3164("2025-01-02T12:00:00+01:00 fw1 filterlog[123]: 96,,,abc"),
+            Some(message(
+                "2025-01-02T12:00:00+01:00",
+                Some("fw1"),
+                Some("123"),
+                None
+            ))
+        );
+    }
+
+    #[test]
+    fn test_rfc5424_header() {
+        let msg =
+            parse_rfc5424("<134>1 2025-01-02T12:00:00+01:00 fw1.local filterlog 123 - - 96,,,abc")
+                .unwrap();
+        let header = msg.header.into_owned();
+        assert_eq!(header.hostname.as_deref(), Some("fw1.local"));
+        assert_eq!(header.app_name.as_deref(), Some("filterlog"));
+        assert_eq!(header.proc_id.as_deref(), Some("123"));
+        assert_eq!(header.facility_name(), Some("local0"));
+        assert_eq!(header.severity_name(), Some("info"));
+
+        let msg = parse_rfc5424("<134>1 - - - - - - 96,,,abc").unwrap();
+        assert_eq!(msg.header.hostname, None);
+        assert_eq!(msg.header.app_name, None);
+    }
+
+    #[test]
+    fn test_rfc3164_invalid() {
+        assert_eq!(parse_rfc3164("<134Jan  2 12:00:00 fw1 filterlog: x"), None);
+        assert_eq!(parse_rfc3164("<abc>Jan  2 12:00:00 fw1 filterlog: x"), None);
+        assert_eq!(parse_rfc3164("<134>Jan  2 12:00:00 fw1 filterlog x"), None);
+        assert_eq!(parse_rfc3164("<134>Jan  2"), None);
+        assert_eq!(parse_rfc3164("96,,,abc,vlan0.20"), None);
+    }
+}
@@ -0,0 +1,286 @@
+use std::{io, net::SocketAddr, time::Duration};
+
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader},
+    net::TcpListener,
+    time,
+};
+
+use super::{line::LineSource, LogIngester, LogSink};
+
+//Upper bound for a single syslog frame, protects against bogus octet counts
+//and peers that never send a newline.
+const MAX_FRAME_SIZE: usize = 64 * 1024;
+//Octet counts longer than this can't be valid, the frame is read as a line instead.
+const MAX_OCTET_COUNT_DIGITS: usize = 10;
+//Pause after a failed accept, running out of file descriptors fails every accept.
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+//Receives syslog messages over TCP, every connected firewall is served by its own task.
+pub struct TcpLogIngester {
+    sink: LogSink,
+    listener: TcpListener,
+}
+
+impl TcpLogIngester {
+    pub async fn new(addr: SocketAddr) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+
+        Ok(Self {
+            sink: LogSink::default(),
+            listener,
+        })
+    }
+}
+
+impl LogIngester for TcpLogIngester {
+    async fn ingest(&mut self) -> io::Result<()> {
+        loop {
+            let stream = match self.listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    accept_failed(e).await;
+                    continue;
+                }
+            };
+
+            let sink = self.sink.clone();
+            tokio::spawn(async move { sink.ingest(&mut Frames::new(stream)).await });
+        }
+    }
+
+    fn sink(&self) -> &LogSink {
+        &self.sink
+    }
+
+    fn sink_mut(&mut self) -> &mut LogSink {
+        &mut self.sink
+    }
+}
+
+//A failed accept usually only affects the peer that was connecting, but when the
+//process is out of file descriptors it fails until one is closed: don't spin on it.
+pub(crate) async fn accept_failed(e: io::Error) {
+    eprintln!("Error accepting a connection: {}", e);
+    time::sleep(ACCEPT_BACKOFF).await;
+}
+
+//Syslog frames read from a stream until the peer disconnects.
+pub(crate) struct Frames<R> {
+    reader: BufReader<R>,
+    frame: Vec<u8>,
+}
+
+impl<R: AsyncRead + Unpin> Frames<R> {
+    pub(crate) fn new(stream: R) -> Self {
+        Self {
+            reader: BufReader::new(stream),
+            frame: Vec::new(),
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> LineSource for Frames<R> {
+    async fn next_line(&mut self) -> io::Result<Option<String>> {
+        let more = read_frame(&mut self.reader, &mut self.frame).await?;
+        Ok(more.then(|| String::from_utf8_lossy(&self.frame).into_owned()))
+    }
+}
+
+//Reads the next frame into `frame`, returns false on end of stream.
+//Supports both framings of RFC 6587: octet counting (`123 <134>1 ...`),
+//detected by `[1-9][0-9]* <`, and non-transparent LF delimited frames.
+//A frame over MAX_FRAME_SIZE is an error, the connection is dropped.
+pub(crate) async fn read_frame<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    frame: &mut Vec<u8>,
+) -> io::Result<bool> {
+    frame.clear();
+
+    let first = loop {
+        match reader.fill_buf().await?.first() {
+            None => return Ok(false),
+            //Skip stray delimiters between frames.
+            Some(b'\n' | b'\r' | b'\0') => reader.consume(1),
+            Some(byte) => break *byte,
+        }
+    };
+
+    let octet_count = match first {
+        b'1'..=b'9' => read_octet_count(reader, frame).await?,
+        _ => None,
+    };
+
+    match octet_count {
+        Some(len) => {
+            if len > MAX_FRAME_SIZE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "invalid octet count",
+                ));
+            }
+            frame.clear();
+            frame.resize(len, 0);
+            reader.read_exact(frame).await?;
+        }
+        None => {
+            //Bytes already looked at for an octet count are part of the line.
+            let limit = (MAX_FRAME_SIZE + 1).saturating_sub(frame.len());
+            (&mut *reader)
+                .take(limit as u64)
+                .read_until(b'\n', frame)
+                .await?;
+            if frame.len() > MAX_FRAME_SIZE && frame.last() != Some(&b'\n') {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "frame too long"));
+            }
+        }
+    }
+
+    while let Some(b'\n' | b'\r' | b'\0') = frame.last() {
+        frame.pop();
+    }
+
+    Ok(true)
+}
+
+//Consumes the digits and the space of an octet count into `frame`, returns the count
+//when they are followed by the `<` of a syslog header.
+async fn read_octet_count<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    frame: &mut Vec<u8>,
+) -> io::Result<Option<usize>> {
+    loop {
+        match reader.fill_buf().await?.first() {
+            Some(byte) if byte.is_ascii_digit() && frame.len() < MAX_OCTET_COUNT_DIGITS => {
+                frame.push(*byte);
+                reader.consume(1);
+            }
+            Some(b' ') => {
+                frame.push(b' ');
+                reader.consume(1);
+                break;
+            }
+            _ => return Ok(None),
+        }
+    }
+
+    if reader.fill_buf().await?.first() != Some(&b'<') {
+        return Ok(None);
+    }
+    let digits = &frame[..frame.len() - 1];
+    Ok(std::str::from_utf8(digits)
+        .ok()
+        .and_then(|len| len.parse().ok()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingesters::tests::SYSLOG_LINE;
+    use std::{str::FromStr, time::Duration};
+    use tokio::{io::AsyncWriteExt, net::TcpStream};
+
+    async fn read_all_frames(input: &[u8]) -> io::Result<Vec<String>> {
+        let mut reader = input;
+        let mut frame = Vec::new();
+        let mut frames = Vec::new();
+        while read_frame(&mut reader, &mut frame).await? {
+            frames.push(String::from_utf8(frame.clone()).unwrap());
+        }
+        Ok(frames)
+    }
+
+    #[tokio::test]
+    async fn test_newline_framing() {
+        let frames = read_all_frames(b"<134>1 first\n<134>1 second\r\n\n<134>1 last")
+            .await
+            .unwrap();
+        assert_eq!(frames, ["<134>1 first", "<134>1 second", "<134>1 last"]);
+    }
+
+    #[tokio::test]
+    async fn test_octet_counting_framing() {
+        let frames = read_all_frames(b"12 <134>1 first13 <134>1 second\n10 <134>1 a\nb")
+            .await
+            .unwrap();
+        assert_eq!(frames, ["<134>1 first", "<134>1 second", "<134>1 a\nb"]);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_octet_count() {
+        assert!(read_all_frames(b"99999999 <134>1").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_digits_without_octet_count() {
+        let frames = read_all_frames(b"12a <134>1 first\n96,,,0,em0,match\n12 not syslog\n0 <1>")
+            .await
+            .unwrap();
+        assert_eq!(
+            frames,
+            [
+                "12a <134>1 first",
+                "96,,,0,em0,match",
+                "12 not syslog",
+                "0 <1>"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_frame_too_long() {
+        let mut line = vec![b'a'; MAX_FRAME_SIZE];
+        line.push(b'\n');
+        assert_eq!(read_all_frames(&line).await.unwrap().len(), 1);
+
+        line.insert(0, b'a');
+        let err = read_all_frames(&line).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_tcp_ingest() {
+        let mut ingester = TcpLogIngester::new(SocketAddr::from_str("127.0.0.1:0").unwrap())
+            .await
+            .unwrap();
+        let addr = ingester.listener.local_addr().unwrap();
+        let logs = ingester.get_logs();
+        tokio::spawn(async move { ingester.ingest().await });
+
+        let mut octet_counted = TcpStream::connect(addr).await.unwrap();
+        let mut lf_delimited = TcpStream::connect(addr).await.unwrap();
+        octet_counted
+            .write_all(format!("{} {}", SYSLOG_LINE.len(), SYSLOG_LINE).as_bytes())
+            .await
+            .unwrap();
+        lf_delimited
+            .write_all(format!("{}\n", SYSLOG_LINE).as_bytes())
+            .await
+            .unwrap();
+        drop(octet_counted);
+        drop(lf_delimited);
+
+        //A reconnecting firewall is served as well.
+        let mut reconnected = TcpStream::connect(addr).await.unwrap();
+        reconnected
+            .write_all(format!("{}\n", SYSLOG_LINE).as_bytes())
+            .await
+            .unwrap();
+
+        //Bare filterlog CSV lines start with the rule number, not an octet count.
+        let (_, payload) = SYSLOG_LINE.split_once("] ").unwrap();
+        let mut bare_csv = TcpStream::connect(addr).await.unwrap();
+        bare_csv
+            .write_all(format!("{}\n", payload).as_bytes())
+            .await
+            .unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while logs.read().unwrap().len() < 4 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
@@ -0,0 +1,230 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Sender},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use chrono::Local;
+
+//Writes every raw line read by the ingesters to a file, so that a live session
+//can be opened again later. The file is rotated once it gets too big or too old,
+//rotated files get the time of the rotation as suffix.
+#[derive(Debug)]
+pub struct Tee {
+    path: PathBuf,
+    //None once writing failed, ingestion goes on without it.
+    file: Option<BufWriter<File>>,
+    written: u64,
+    opened: Instant,
+    max_size: Option<u64>,
+    max_age: Option<Duration>,
+}
+
+impl Tee {
+    //Lines are appended to an existing file.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            written: file.metadata()?.len(),
+            path,
+            file: Some(BufWriter::new(file)),
+            opened: Instant::now(),
+            max_size: None,
+            max_age: None,
+        })
+    }
+
+    pub fn max_size(mut self, max_size: Option<u64>) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    pub fn max_age(mut self, max_age: Option<Duration>) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    //Writes the lines sent by the ingesters on its own thread, so that they never wait
+    //for the disk. Lines are buffered and flushed whenever no more lines are pending.
+    //The thread stops once a writer is closed, join it to be sure every line was written.
+    pub fn spawn(mut self) -> (TeeWriter, JoinHandle<()>) {
+        let (sender, receiver) = mpsc::channel::<Option<String>>();
+        let handle = thread::spawn(move || {
+            while let Ok(Some(raw_log)) = receiver.recv() {
+                self.write_line(&raw_log);
+                let mut closed = false;
+                for raw_log in receiver.try_iter() {
+                    match raw_log {
+                        Some(raw_log) => self.write_line(&raw_log),
+                        None => {
+                            closed = true;
+                            break;
+                        }
+                    }
+                }
+                self.flush();
+                if closed {
+                    break;
+                }
+            }
+        });
+        (TeeWriter(sender), handle)
+    }
+
+    pub fn write_line(&mut self, raw_log: &str) {
+        if self.should_rotate(raw_log.len() as u64 + 1) && self.rotate().is_err() {
+            self.file = None;
+        }
+        let Some(file) = &mut self.file else {
+            return;
+        };
+        match writeln!(file, "{}", raw_log) {
+            Ok(()) => self.written += raw_log.len() as u64 + 1,
+            Err(_) => self.file = None,
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if let Some(file) = &mut self.file {
+            if file.flush().is_err() {
+                self.file = None;
+            }
+        }
+    }
+
+    fn should_rotate(&self, line_size: u64) -> bool {
+        self.written > 0
+            && (self
+                .max_size
+                .is_some_and(|max_size| self.written + line_size > max_size)
+                || self
+                    .max_age
+                    .is_some_and(|max_age| self.opened.elapsed() >= max_age))
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if let Some(file) = &mut self.file {
+            file.flush()?;
+        }
+        let suffix = Local::now().format("%Y%m%d-%H%M%S").to_string();
+        let mut rotated = Self::rotated_path(&self.path, &suffix, 0);
+        let mut n = 0;
+        while rotated.exists() {
+            n += 1;
+            rotated = Self::rotated_path(&self.path, &suffix, n);
+        }
+
+        fs::rename(&self.path, rotated)?;
+        self.file = Some(BufWriter::new(File::create(&self.path)?));
+        self.written = 0;
+        self.opened = Instant::now();
+        Ok(())
+    }
+
+    fn rotated_path(path: &Path, suffix: &str, n: usize) -> PathBuf {
+        let mut rotated = path.as_os_str().to_owned();
+        rotated.push(".");
+        rotated.push(suffix);
+        if n > 0 {
+            rotated.push(format!("-{}", n));
+        }
+        PathBuf::from(rotated)
+    }
+}
+
+//Sends raw lines to the thread of a `Tee`, shared by every connection of an ingester.
+#[derive(Debug, Clone)]
+pub struct TeeWriter(Sender<Option<String>>);
+
+impl TeeWriter {
+    pub fn write_line(&self, raw_log: &str) {
+        //Lines sent after the thread stopped are dropped.
+        let _ = self.0.send(Some(raw_log.to_string()));
+    }
+
+    //The ingesters may still hold writers on exit, so the thread is told to stop
+    //once the lines already sent are written.
+    pub fn close(&self) {
+        let _ = self.0.send(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingesters::{
+        line::LineIngester,
+        tests::{syslog_line_at, TempDir, SYSLOG_LINE},
+        LogIngester,
+    };
+    use tokio::io::AsyncBufReadExt;
+
+    fn read_dir(dir: &Path) -> Vec<String> {
+        let mut files: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_rotate_by_size() {
+        let dir = TempDir::new("tee");
+        let path = dir.join("live.log");
+        fs::write(&path, "previous\n").unwrap();
+
+        let mut tee = Tee::create(&path).unwrap().max_size(Some(19));
+        tee.write_line("first");
+        tee.write_line("");
+        tee.write_line("second line");
+        tee.write_line("garbage");
+        tee.flush();
+
+        assert_eq!(
+            read_dir(&dir),
+            ["garbage\n", "previous\nfirst\n\n", "second line\n"]
+        );
+    }
+
+    #[test]
+    fn test_rotate_by_age() {
+        let dir = TempDir::new("tee-age");
+        let path = dir.join("live.log");
+
+        let mut tee = Tee::create(&path).unwrap().max_age(Some(Duration::ZERO));
+        tee.write_line("first");
+        tee.write_line("second");
+        tee.write_line("third");
+        tee.flush();
+
+        //Rotations within the same second don't overwrite each other.
+        assert_eq!(read_dir(&dir), ["first\n", "second\n", "third\n"]);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "third\n");
+    }
+
+    #[tokio::test]
+    async fn test_tee_every_line() {
+        let dir = TempDir::new("tee-all");
+        let path = dir.join("live.log");
+
+        let input = format!("{}\ngarbage\n{}\n", SYSLOG_LINE, syslog_line_at("-"));
+        let (tee, handle) = Tee::create(&path).unwrap().spawn();
+        let mut ingester = LineIngester::new(input.as_bytes().lines());
+        ingester.sink_mut().set_tee(Some(tee.clone()));
+        ingester.ingest().await.unwrap();
+        assert_eq!(ingester.get_logs().read().unwrap().len(), 2);
+
+        //Every line sent before closing is written, even though the ingester keeps its writer.
+        tee.close();
+        handle.join().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), input);
+
+        tee.write_line("after close");
+        assert_eq!(fs::read_to_string(&path).unwrap(), input);
+    }
+}
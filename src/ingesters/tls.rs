@@ -0,0 +1,245 @@
+use std::{
+    io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use tokio::{net::TcpListener, time};
+use tokio_rustls::{
+    rustls::{
+        pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+        server::WebPkiClientVerifier,
+        RootCertStore, ServerConfig,
+    },
+    TlsAcceptor,
+};
+
+use super::{
+    tcp::{accept_failed, Frames},
+    LogIngester, LogSink,
+};
+
+//Certificates used by the TLS syslog listener (RFC 5425).
+#[derive(Debug, Clone)]
+pub struct TlsOptions {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+    //When set, clients must present a certificate signed by this CA.
+    pub client_ca: Option<PathBuf>,
+}
+
+impl TlsOptions {
+    fn server_config(&self) -> io::Result<ServerConfig> {
+        let certs = CertificateDer::pem_file_iter(&self.cert)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| invalid_input(&self.cert, e))?;
+        let key =
+            PrivateKeyDer::from_pem_file(&self.key).map_err(|e| invalid_input(&self.key, e))?;
+
+        let builder = ServerConfig::builder();
+        let builder = match &self.client_ca {
+            Some(client_ca) => {
+                let mut roots = RootCertStore::empty();
+                for cert in CertificateDer::pem_file_iter(client_ca)
+                    .map_err(|e| invalid_input(client_ca, e))?
+                {
+                    roots
+                        .add(cert.map_err(|e| invalid_input(client_ca, e))?)
+                        .map_err(|e| invalid_input(client_ca, e))?;
+                }
+
+                let verifier = WebPkiClientVerifier::builder(Arc::new(roots))
+                    .build()
+                    .map_err(|e| invalid_input(client_ca, e))?;
+                builder.with_client_cert_verifier(verifier)
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        builder
+            .with_single_cert(certs, key)
+            .map_err(|e| invalid_input(&self.cert, e))
+    }
+}
+
+//Time a client gets to complete the handshake, so that stalled ones don't hold a task.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn invalid_input(path: &Path, e: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("{}: {}", path.display(), e),
+    )
+}
+
+//Receives syslog messages over TLS, framing is the same as for plain TCP.
+pub struct TlsLogIngester {
+    sink: LogSink,
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+}
+
+impl TlsLogIngester {
+    pub async fn new(addr: SocketAddr, options: &TlsOptions) -> io::Result<Self> {
+        let acceptor = TlsAcceptor::from(Arc::new(options.server_config()?));
+        let listener = TcpListener::bind(addr).await?;
+
+        Ok(Self {
+            sink: LogSink::default(),
+            listener,
+            acceptor,
+        })
+    }
+}
+
+impl LogIngester for TlsLogIngester {
+    async fn ingest(&mut self) -> io::Result<()> {
+        loop {
+            let stream = match self.listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    accept_failed(e).await;
+                    continue;
+                }
+            };
+
+            let acceptor = self.acceptor.clone();
+            let sink = self.sink.clone();
+            tokio::spawn(async move {
+                let stream = time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await??;
+                sink.ingest(&mut Frames::new(stream)).await
+            });
+        }
+    }
+
+    fn sink(&self) -> &LogSink {
+        &self.sink
+    }
+
+    fn sink_mut(&mut self) -> &mut LogSink {
+        &mut self.sink
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingesters::tests::{TempDir, SYSLOG_LINE};
+    use rcgen::CertifiedKey;
+    use std::{fs, str::FromStr};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    };
+    use tokio_rustls::{
+        rustls::{pki_types::ServerName, ClientConfig},
+        TlsConnector,
+    };
+
+    //Writes a self-signed certificate for localhost and its key to `dir`.
+    fn write_self_signed(dir: &Path) -> CertificateDer<'static> {
+        let CertifiedKey { cert, key_pair } =
+            rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("cert.pem"), cert.pem()).unwrap();
+        fs::write(dir.join("key.pem"), key_pair.serialize_pem()).unwrap();
+        cert.der().clone()
+    }
+
+    #[tokio::test]
+    async fn test_tls_ingest() {
+        let dir = TempDir::new("tls");
+        let cert = write_self_signed(&dir);
+
+        let options = TlsOptions {
+            cert: dir.join("cert.pem"),
+            key: dir.join("key.pem"),
+            client_ca: None,
+        };
+        let mut ingester =
+            TlsLogIngester::new(SocketAddr::from_str("127.0.0.1:0").unwrap(), &options)
+                .await
+                .unwrap();
+        let addr = ingester.listener.local_addr().unwrap();
+        let logs = ingester.get_logs();
+        tokio::spawn(async move { ingester.ingest().await });
+
+        let mut roots = RootCertStore::empty();
+        roots.add(cert).unwrap();
+        let config = ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = TlsConnector::from(Arc::new(config))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await
+            .unwrap();
+        stream
+            .write_all(format!("{} {}", SYSLOG_LINE.len(), SYSLOG_LINE).as_bytes())
+            .await
+            .unwrap();
+        stream.shutdown().await.unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while logs.read().unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_client_certificate_required() {
+        let dir = TempDir::new("tls-ca");
+        let cert = write_self_signed(&dir);
+
+        let options = TlsOptions {
+            cert: dir.join("cert.pem"),
+            key: dir.join("key.pem"),
+            client_ca: Some(dir.join("cert.pem")),
+        };
+        let mut ingester =
+            TlsLogIngester::new(SocketAddr::from_str("127.0.0.1:0").unwrap(), &options)
+                .await
+                .unwrap();
+        let addr = ingester.listener.local_addr().unwrap();
+        let logs = ingester.get_logs();
+        tokio::spawn(async move { ingester.ingest().await });
+
+        let mut roots = RootCertStore::empty();
+        roots.add(cert).unwrap();
+        let config = ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let connected = TlsConnector::from(Arc::new(config))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await;
+        //With TLS 1.3 the client only learns that it was rejected after its handshake.
+        if let Ok(mut stream) = connected {
+            let _ = stream
+                .write_all(format!("{} {}", SYSLOG_LINE.len(), SYSLOG_LINE).as_bytes())
+                .await;
+            let mut buf = [0; 1];
+            let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf))
+                .await
+                .unwrap();
+            assert!(!matches!(read, Ok(n) if n > 0));
+        }
+        assert!(logs.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_missing_key() {
+        let options = TlsOptions {
+            cert: "/nonexistent/cert.pem".into(),
+            key: "/nonexistent/key.pem".into(),
+            client_ca: None,
+        };
+        let err = options.server_config().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,73 @@
+use std::{io, net::SocketAddr};
+
+use tokio::net::UdpSocket;
+
+use super::line::{LineIngester, LineSource};
+
+//Largest payload a single UDP datagram can carry.
+const MAX_DATAGRAM_SIZE: usize = 65_535;
+
+pub type UdpLogIngester = LineIngester<UdpSource>;
+
+//Syslog messages sent by the firewall to a remote logging target,
+//each datagram carries exactly one message.
+pub struct UdpSource {
+    socket: UdpSocket,
+    buf: Vec<u8>,
+}
+
+impl UdpSource {
+    pub async fn new(addr: SocketAddr) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr).await?;
+
+        Ok(Self {
+            socket,
+            buf: vec![0u8; MAX_DATAGRAM_SIZE],
+        })
+    }
+}
+
+impl LineSource for UdpSource {
+    async fn next_line(&mut self) -> io::Result<Option<String>> {
+        let len = self.socket.recv(&mut self.buf).await?;
+        let raw_log = String::from_utf8_lossy(&self.buf[..len]);
+        //Some senders terminate the message with a newline or a NUL byte.
+        let raw_log = raw_log.trim_end_matches(['\n', '\r', '\0']);
+
+        Ok(Some(raw_log.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingesters::{tests::SYSLOG_LINE, LogIngester};
+    use std::{str::FromStr, time::Duration};
+
+    #[tokio::test]
+    async fn test_udp_ingest() {
+        let source = UdpSource::new(SocketAddr::from_str("127.0.0.1:0").unwrap())
+            .await
+            .unwrap();
+        let addr = source.socket.local_addr().unwrap();
+        let mut ingester = UdpLogIngester::new(source);
+        let logs = ingester.get_logs();
+        let notify = ingester.get_notify();
+        tokio::spawn(async move { ingester.ingest().await });
+
+        let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        sender.send_to(b"garbage", addr).await.unwrap();
+        sender
+            .send_to(format!("{}\n", SYSLOG_LINE).as_bytes(), addr)
+            .await
+            .unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), notify.notified())
+            .await
+            .unwrap();
+
+        let logs = logs.read().unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs.get(0).unwrap().log.packet_filter.interface, "vlan0.20");
+    }
+}
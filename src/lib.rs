@@ -0,0 +1,28 @@
+#![doc = include_str!("../README.md")]
+use chrono::Local;
+use ingesters::syslog::SyslogHeader;
+use senpa::FwLog;
+use timestamp::TimestampSource;
+
+pub mod action;
+pub mod app;
+pub mod cidr;
+pub mod cli;
+pub mod filter;
+pub mod ingesters;
+pub mod packet_filter;
+pub mod port_filter;
+pub mod store;
+pub mod tcp_flags;
+pub mod timestamp;
+pub mod ui;
+
+#[derive(Clone)]
+pub struct TimestampedLog {
+    pub timestamp: chrono::DateTime<Local>,
+    pub timestamp_source: TimestampSource,
+    pub header: SyslogHeader,
+    //pf reason as written in the line, the parsed log can only represent `match`.
+    pub reason: String,
+    pub log: FwLog,
+}
@@ -1,30 +1,31 @@
-#![doc = include_str!("../README.md")]
-use app::App;
-use chrono::Local;
 use clap::Parser;
-use cli::Cli;
-use packet_filter::LogFilter;
-use senpa::FwLog;
+use firetail::{
+    app::App,
+    cli::Cli,
+    ingesters::{
+        command::{CommandLogIngester, CommandSource},
+        file_log::{FileLogIngester, FileSource},
+        mapped::MappedFileIngester,
+        multi_file::MultiFileLogIngester,
+        parallel::ParallelFileIngester,
+        parser::{FilterlogParser, LogParser},
+        replay::ReplayIngester,
+        stdin::StdinLogIngester,
+        tcp::TcpLogIngester,
+        tee::Tee,
+        tls::TlsLogIngester,
+        udp::{UdpLogIngester, UdpSource},
+        ListenAddr, LogIngester, LogSink,
+    },
+    packet_filter::LogFilter,
+};
 use std::{
+    fs::File,
     io::{self},
     process::exit,
+    str::FromStr,
+    sync::Arc,
 };
-mod cidr;
-mod cli;
-mod ingesters;
-mod port_filter;
-mod filter;
-mod packet_filter;
-mod ui;
-mod action;
-mod app;
-use ingesters::{file_log::FileLogIngester, stdin::StdinLogIngester, LogIngester};
-
-#[derive(Clone)]
-pub struct TimestampedLog {
-    pub timestamp: chrono::DateTime<Local>,
-    pub log: FwLog,
-}
 
 #[tokio::main]
 async fn main() -> io::Result<()> {
@@ -33,34 +34,258 @@ async fn main() -> io::Result<()> {
     let filter = match cli.build_filter() {
         Ok(filter) => filter,
         Err(e) => {
-            println!("{:?}",e);
+            eprintln!("{}", e);
+            exit(1);
+        },
+    };
+
+    let listen_addr = match cli.listen.as_deref().map(ListenAddr::from_str) {
+        Some(Ok(addr)) => Some(addr),
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+        None => None,
+    };
+
+    let tls_options = cli.tls_options();
+    let tls_requested =
+        cli.tls_cert.is_some() || cli.tls_key.is_some() || cli.tls_client_ca.is_some();
+    if tls_requested && !matches!(listen_addr, Some(ListenAddr::Tls(_))) {
+        eprintln!("--tls-cert, --tls-key and --tls-client-ca require a tls:// listen address");
+        exit(1);
+    }
+
+    let log_files = match cli.log_files() {
+        Ok(log_files) => log_files,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    };
+
+    if cli.follow && log_files.len() > 1 {
+        eprintln!("--follow requires a single log file");
+        exit(1);
+    }
+
+    if cli.lazy && log_files.len() > 1 {
+        eprintln!("--lazy requires a single log file");
+        exit(1);
+    }
+
+    if cli.replay.is_some() && log_files.len() > 1 {
+        eprintln!("--replay requires a single log file");
+        exit(1);
+    }
+
+    let window = match cli.time_window() {
+        Ok(window) => window,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    };
+    if let (Some(since), Some(until)) = (window.since, window.until) {
+        if since > until {
+            eprintln!("--since is after --until");
             exit(1);
+        }
+    }
+
+    let tee = match &cli.tee {
+        Some(path) => match Tee::create(path) {
+            Ok(tee) => {
+                let tee = tee
+                    .max_size(cli.tee_max_size.map(|size| size as u64))
+                    .max_age(cli.tee_max_age);
+                Some(tee.spawn())
+            }
+            Err(e) => {
+                eprintln!("Error opening {}: {}", path.display(), e);
+                exit(2);
+            }
+        },
+        None => None,
+    };
+    let (tee, tee_thread) = tee.unzip();
+
+    //Opened before ingestion starts, so that every rejected line is dumped.
+    let rejects_dump = match &cli.dump_rejects {
+        Some(path) => match File::create(path) {
+            Ok(dump) => Some(dump),
+            Err(e) => {
+                eprintln!("Error opening {}: {}", path.display(), e);
+                exit(2);
+            }
         },
+        None => None,
     };
 
-    let (parsed_log, notify) = match cli.logfile {
-        Some(log_file) => match FileLogIngester::new(log_file).await {
+    let mut command_status = None;
+    let mut replay_control = None;
+    let parser: Arc<dyn LogParser> = Arc::new(FilterlogParser::new(cli.format));
+    //Every ingester parses, keeps and copies its lines the same way.
+    let configure = |sink: &mut LogSink| {
+        sink.set_parser(parser.clone());
+        sink.set_window(window);
+        sink.set_tee(tee.clone());
+        sink.set_limits(cli.max_logs, cli.max_memory);
+        sink.set_rejects_dump(rejects_dump);
+    };
+
+    let (parsed_log, notify, rejects) = match (log_files.as_slice(), listen_addr, cli.exec.clone())
+    {
+        (_, _, Some(exec)) => {
+            let source = CommandSource::new(exec).restart(cli.restart);
+            command_status = Some(source.get_status());
+            let mut ingester = CommandLogIngester::new(source);
+            configure(ingester.sink_mut());
+            let logs = ingester.get_logs();
+            let notify = ingester.get_notify();
+            let rejects = ingester.get_rejects();
+            tokio::spawn(async move { ingester.ingest().await });
+            (logs, notify, rejects)
+        }
+        ([log_file], None, _) if cli.replay.is_some() => match FileSource::new(log_file).await {
+            Ok(source) => {
+                let mut ingester = ReplayIngester::new(source, cli.replay.unwrap());
+                configure(ingester.sink_mut());
+                replay_control = Some(ingester.get_control());
+                let logs = ingester.get_logs();
+                let notify = ingester.get_notify();
+                let rejects = ingester.get_rejects();
+                tokio::spawn(async move { ingester.ingest().await });
+                (logs, notify, rejects)
+            }
+            Err(e) => {
+                eprintln!("Error initializing log ingester: {}", e);
+                exit(2);
+            }
+        },
+        ([log_file], None, _) if cli.lazy => match MappedFileIngester::new(log_file) {
+            Ok(ingester) => {
+                let mut ingester = ingester.cache(cli.index_cache);
+                configure(ingester.sink_mut());
+                let logs = ingester.get_logs();
+                let notify = ingester.get_notify();
+                let rejects = ingester.get_rejects();
+                tokio::spawn(async move { ingester.ingest().await });
+                (logs, notify, rejects)
+            }
+            Err(e) => {
+                eprintln!("Error initializing log ingester: {}", e);
+                exit(2);
+            }
+        },
+        //The parallel ingester doesn't read lines one by one, so it can't tee them.
+        ([log_file], None, _) if !cli.follow && tee.is_none() => {
+            match ParallelFileIngester::new(log_file) {
+                Ok(mut ingester) => {
+                    configure(ingester.sink_mut());
+                    let logs = ingester.get_logs();
+                    let notify = ingester.get_notify();
+                    let rejects = ingester.get_rejects();
+                    tokio::spawn(async move { ingester.ingest().await });
+                    (logs, notify, rejects)
+                }
+                Err(e) => {
+                    eprintln!("Error initializing log ingester: {}", e);
+                    exit(2);
+                }
+            }
+        }
+        ([log_file], None, _) => match FileSource::new(log_file).await {
+            Ok(source) => {
+                let mut ingester = FileLogIngester::new(source.follow(cli.follow));
+                configure(ingester.sink_mut());
+                let logs = ingester.get_logs();
+                let notify = ingester.get_notify();
+                let rejects = ingester.get_rejects();
+                tokio::spawn(async move { ingester.ingest().await });
+                (logs, notify, rejects)
+            }
+            Err(e) => {
+                eprintln!("Error initializing log ingester: {}", e);
+                exit(2);
+            }
+        },
+        ([_, _, ..], None, _) => match MultiFileLogIngester::new(log_files.clone()) {
             Ok(mut ingester) => {
+                configure(ingester.sink_mut());
+                let logs = ingester.get_logs();
+                let notify = ingester.get_notify();
+                let rejects = ingester.get_rejects();
+                tokio::spawn(async move { ingester.ingest().await });
+                (logs, notify, rejects)
+            }
+            Err(e) => {
+                eprintln!("Error initializing log ingester: {}", e);
+                exit(2);
+            }
+        },
+        (_, Some(ListenAddr::Udp(addr)), _) => match UdpSource::new(addr).await {
+            Ok(source) => {
+                let mut ingester = UdpLogIngester::new(source);
+                configure(ingester.sink_mut());
                 let logs = ingester.get_logs();
                 let notify = ingester.get_notify();
+                let rejects = ingester.get_rejects();
                 tokio::spawn(async move { ingester.ingest().await });
-                (logs, notify)
+                (logs, notify, rejects)
             }
             Err(e) => {
                 eprintln!("Error initializing log ingester: {}", e);
                 exit(2);
             }
         },
-        None => {
-            let mut ingester = StdinLogIngester::new();
+        (_, Some(ListenAddr::Tcp(addr)), _) => match TcpLogIngester::new(addr).await {
+            Ok(mut ingester) => {
+                configure(ingester.sink_mut());
+                let logs = ingester.get_logs();
+                let notify = ingester.get_notify();
+                let rejects = ingester.get_rejects();
+                tokio::spawn(async move { ingester.ingest().await });
+                (logs, notify, rejects)
+            }
+            Err(e) => {
+                eprintln!("Error initializing log ingester: {}", e);
+                exit(2);
+            }
+        },
+        (_, Some(ListenAddr::Tls(addr)), _) => {
+            let Some(tls_options) = tls_options else {
+                eprintln!("A TLS listener requires both --tls-cert and --tls-key");
+                exit(1);
+            };
+            match TlsLogIngester::new(addr, &tls_options).await {
+                Ok(mut ingester) => {
+                    configure(ingester.sink_mut());
+                    let logs = ingester.get_logs();
+                    let notify = ingester.get_notify();
+                    let rejects = ingester.get_rejects();
+                    tokio::spawn(async move { ingester.ingest().await });
+                    (logs, notify, rejects)
+                }
+                Err(e) => {
+                    eprintln!("Error initializing log ingester: {}", e);
+                    exit(2);
+                }
+            }
+        }
+        ([], None, _) => {
+            let mut ingester = StdinLogIngester::stdin();
+            configure(ingester.sink_mut());
             let logs = ingester.get_logs();
             let notify = ingester.get_notify();
+            let rejects = ingester.get_rejects();
             tokio::spawn(async move { ingester.ingest().await });
-            (logs, notify)
+            (logs, notify, rejects)
         }
     };
 
-    let mut log_filter = LogFilter::new(parsed_log.clone(), notify.clone()).filter(filter);
+    let mut log_filter = LogFilter::new(parsed_log.clone(), notify.clone()).filter(filter.clone());
+    let filter_control = log_filter.get_control();
 
     let filtered_logs = log_filter.get_filtered_logs();
 
@@ -70,11 +295,25 @@ async fn main() -> io::Result<()> {
 
     let mut terminal = ratatui::init();
 
-    let mut app = App::new(parsed_log.clone(), filtered_logs.clone());
+    let mut app = App::new(parsed_log.clone(), filtered_logs.clone(), rejects)
+        .zone(cli.tz)
+        .filter(filter, cli.where_expr.clone(), filter_control);
+    if let Some(command_status) = command_status {
+        app = app.command_status(command_status);
+    }
+    if let Some(replay_control) = replay_control {
+        app = app.replay(replay_control);
+    }
 
     let app_result = app.run(&mut terminal).await;
 
     ratatui::restore();
 
+    //Lines read before quitting are still written to the tee.
+    if let (Some(tee), Some(tee_thread)) = (tee, tee_thread) {
+        tee.close();
+        let _ = tee_thread.join();
+    }
+
     app_result
 }
@@ -1,26 +1,73 @@
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 
 use tokio::sync::Notify;
 
-use crate::{filter::Filter, TimestampedLog};
+use crate::{
+    filter::Filter,
+    store::{LogStore, Ring},
+};
+
+//Logs tested between two yields while filtering the whole store again.
+const REBUILD_CHUNK: usize = 10_000;
+//Mapped logs are parsed to be tested, fewer of them are tested under a single lock.
+const MAPPED_REBUILD_CHUNK: usize = 500;
+
+//State of the filter, shown in the ui.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FilterStatus {
+    pub rebuilding: bool,
+    //Incremented each time filtered_logs is replaced, its positions start over.
+    pub generation: usize,
+}
+
+//Lets the ui replace the filter while logs are processed.
+#[derive(Debug, Default)]
+pub struct FilterControl {
+    pending: Mutex<Option<Filter>>,
+    status: RwLock<FilterStatus>,
+    changed: Notify,
+}
+
+impl FilterControl {
+    pub fn status(&self) -> FilterStatus {
+        *self.status.read().unwrap()
+    }
+
+    //Filters every stored log again with `filter`, a previous rebuild still running is dropped.
+    pub fn set_filter(&self, filter: Filter) {
+        *self.pending.lock().unwrap() = Some(filter);
+        self.status.write().unwrap().rebuilding = true;
+        self.changed.notify_one();
+    }
+
+    fn take_filter(&self) -> Option<Filter> {
+        self.pending.lock().unwrap().take()
+    }
+
+    fn has_pending(&self) -> bool {
+        self.pending.lock().unwrap().is_some()
+    }
+}
 
 pub struct LogFilter {
-    all_packets: Arc<RwLock<Vec<TimestampedLog>>>,
+    all_packets: Arc<RwLock<LogStore>>,
     filter: Option<Filter>,
     //Contains the index of packet that comply with the filter.
-    filtered_logs: Arc<RwLock<Vec<usize>>>,
+    filtered_logs: Arc<RwLock<Ring<usize>>>,
     log_notify: Arc<Notify>,
     last_processed_packet: usize,
+    control: Arc<FilterControl>,
 }
 
 impl LogFilter {
-    pub fn new(all_packets: Arc<RwLock<Vec<TimestampedLog>>>, log_notify: Arc<Notify>) -> Self {
+    pub fn new(all_packets: Arc<RwLock<LogStore>>, log_notify: Arc<Notify>) -> Self {
         Self {
             filter: None,
-            filtered_logs: Arc::new(RwLock::new(Vec::new())),
+            filtered_logs: Arc::new(RwLock::new(Ring::default())),
             log_notify,
             all_packets,
             last_processed_packet: 0,
+            control: Arc::new(FilterControl::default()),
         }
     }
 
@@ -29,27 +76,79 @@ impl LogFilter {
         self
     }
 
-    pub fn get_filtered_logs(&self) -> Arc<RwLock<Vec<usize>>> {
+    pub fn get_filtered_logs(&self) -> Arc<RwLock<Ring<usize>>> {
         self.filtered_logs.clone()
     }
 
-    //TODO allow to change filter in realtime.
+    pub fn get_control(&self) -> Arc<FilterControl> {
+        self.control.clone()
+    }
+
     pub async fn process(&mut self) {
         loop {
-            //Wait for new packets
-            self.log_notify.notified().await;
-            self.filter_logs();
+            //Wait for new packets or a new filter
+            tokio::select! {
+                _ = self.log_notify.notified() => self.filter_logs(),
+                _ = self.control.changed.notified() => {
+                    while let Some(filter) = self.control.take_filter() {
+                        self.rebuild(filter).await;
+                    }
+                }
+            }
         }
     }
 
+    //Filters the whole store with `filter` into a new ring, by chunks so that ingesters
+    //and the ui don't wait for it. The ring is swapped in once it is complete,
+    //gives up when another filter is set in the meantime.
+    pub async fn rebuild(&mut self, filter: Filter) {
+        let mut filtered_logs = Ring::default();
+        let mut index = 0;
+        loop {
+            if self.control.has_pending() {
+                return;
+            }
+            {
+                let logs = self.all_packets.read().unwrap();
+                index = index.max(logs.first());
+                let chunk = match logs.mapped_logs() {
+                    Some(_) => MAPPED_REBUILD_CHUNK,
+                    None => REBUILD_CHUNK,
+                };
+                let end = logs.end().min(index + chunk);
+                if index == end {
+                    break;
+                }
+                for index in index..end {
+                    if logs.get(index).is_some_and(|log| filter.test(&log)) {
+                        filtered_logs.push(index);
+                    }
+                }
+                index = end;
+            }
+            tokio::task::yield_now().await;
+        }
+
+        self.filter = Some(filter);
+        //Logs pushed from now on are filtered by filter_logs.
+        self.last_processed_packet = index;
+        let mut current = self.filtered_logs.write().unwrap();
+        *current = filtered_logs;
+        //Updated while the ring is locked, so the generation read along with it matches.
+        let mut status = self.control.status.write().unwrap();
+        status.generation += 1;
+        status.rebuilding = self.control.has_pending();
+    }
+
     pub fn filter_logs(&mut self) {
         let logs = self.all_packets.read().unwrap();
-        let start_index = self.last_processed_packet;
-        let end_index = logs.len();
+        //Logs evicted before being processed are skipped.
+        let start_index = self.last_processed_packet.max(logs.first());
+        let end_index = logs.end();
 
         for index in start_index..end_index {
             if let Some(filt) = &self.filter {
-                if filt.test(&logs[index]) {
+                if logs.get(index).is_some_and(|log| filt.test(&log)) {
                     self.filtered_logs.write().unwrap().push(index);
                 }
             } else {
@@ -57,6 +156,107 @@ impl LogFilter {
             }
         }
 
+        //Drop the indices of evicted logs.
+        let mut filtered_logs = self.filtered_logs.write().unwrap();
+        while filtered_logs
+            .front()
+            .is_some_and(|&index| index < logs.first())
+        {
+            filtered_logs.pop_front();
+        }
+
         self.last_processed_packet = end_index;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingesters::{
+        parser::{FilterlogParser, LogParser},
+        tests::SYSLOG_LINE,
+    };
+
+    #[tokio::test]
+    async fn test_rebuild() {
+        let logs = Arc::new(RwLock::new(LogStore::default()));
+        let mut log_filter = LogFilter::new(logs.clone(), Arc::new(Notify::new()));
+        let filtered_logs = log_filter.get_filtered_logs();
+        let control = log_filter.get_control();
+
+        let parser = FilterlogParser::default();
+        let matching = parser.parse(SYSLOG_LINE).unwrap();
+        let other = parser
+            .parse(&SYSLOG_LINE.replace("vlan0.20", "vlan0.30"))
+            .unwrap();
+        for log in [&matching, &other, &matching, &other] {
+            logs.write().unwrap().push(log.clone());
+        }
+        log_filter.filter_logs();
+        assert_eq!(filtered_logs.read().unwrap().len(), 4);
+
+        let mut filter = Filter::new();
+        filter.add_interface("vlan0.30".into());
+        control.set_filter(filter);
+        assert!(control.status().rebuilding);
+        log_filter.rebuild(control.take_filter().unwrap()).await;
+
+        assert_eq!(
+            filtered_logs
+                .read()
+                .unwrap()
+                .iter()
+                .copied()
+                .collect::<Vec<_>>(),
+            [1, 3]
+        );
+        assert_eq!(
+            control.status(),
+            FilterStatus {
+                rebuilding: false,
+                generation: 1
+            }
+        );
+
+        //Logs pushed after the rebuild go through the new filter.
+        logs.write().unwrap().push(other.clone());
+        logs.write().unwrap().push(matching.clone());
+        log_filter.filter_logs();
+        assert_eq!(
+            filtered_logs
+                .read()
+                .unwrap()
+                .iter()
+                .copied()
+                .collect::<Vec<_>>(),
+            [1, 3, 4]
+        );
+    }
+
+    #[test]
+    fn test_evicted_logs() {
+        let logs = Arc::new(RwLock::new(LogStore::default()));
+        logs.write().unwrap().set_limits(Some(3), None);
+        let mut log_filter = LogFilter::new(logs.clone(), Arc::new(Notify::new()));
+        let filtered_logs = log_filter.get_filtered_logs();
+
+        let log = FilterlogParser::default().parse(SYSLOG_LINE).unwrap();
+        for _ in 0..2 {
+            logs.write().unwrap().push(log.clone());
+        }
+        log_filter.filter_logs();
+        assert_eq!(filtered_logs.read().unwrap().iter().count(), 2);
+
+        for _ in 0..4 {
+            logs.write().unwrap().push(log.clone());
+        }
+        log_filter.filter_logs();
+
+        let filtered_logs = filtered_logs.read().unwrap();
+        //Log 2 was evicted before being filtered.
+        assert_eq!(filtered_logs.iter().copied().collect::<Vec<_>>(), [3, 4, 5]);
+        assert_eq!(filtered_logs.first(), 2);
+        assert_eq!(filtered_logs.end(), 5);
+        assert_eq!(filtered_logs.len(), 3);
+    }
+}
@@ -17,6 +17,9 @@ impl FromStr for Ports {
             Some((start_str, end_str)) => {
                 let start = u16::from_str(start_str).map_err(|_| ParsePortError)?;
                 let end = u16::from_str(end_str).map_err(|_| ParsePortError)?;
+                if start > end {
+                    return Err(ParsePortError);
+                }
                 Ok(Ports::PortRange(start, end))
             }
             None => {
@@ -35,7 +38,7 @@ mod tests {
     fn test_parse_port_range() {
         let port_range_str_valid = "80-443";
         assert_eq!(
-            Ports::from_str(&port_range_str_valid),
+            Ports::from_str(port_range_str_valid),
             Ok(Ports::PortRange(80, 443))
         );
         let invalid0 = "a-443";
@@ -44,29 +47,53 @@ mod tests {
         let invalid3 = "-b";
         let invalid4 = "a-b";
         let invalid5 = "-";
-        assert_eq!(Ports::from_str(&invalid0), Err(ParsePortError));
-        assert_eq!(Ports::from_str(&invalid1), Err(ParsePortError));
-        assert_eq!(Ports::from_str(&invalid2), Err(ParsePortError));
-        assert_eq!(Ports::from_str(&invalid3), Err(ParsePortError));
-        assert_eq!(Ports::from_str(&invalid4), Err(ParsePortError));
-        assert_eq!(Ports::from_str(&invalid5), Err(ParsePortError));
+        let invalid6 = "443-80";
+        assert_eq!(Ports::from_str(invalid0), Err(ParsePortError));
+        assert_eq!(Ports::from_str(invalid1), Err(ParsePortError));
+        assert_eq!(Ports::from_str(invalid2), Err(ParsePortError));
+        assert_eq!(Ports::from_str(invalid3), Err(ParsePortError));
+        assert_eq!(Ports::from_str(invalid4), Err(ParsePortError));
+        assert_eq!(Ports::from_str(invalid5), Err(ParsePortError));
+        assert_eq!(Ports::from_str(invalid6), Err(ParsePortError));
+        assert_eq!(Ports::from_str("80-80"), Ok(Ports::PortRange(80, 80)));
     }
 
     #[test]
     fn test_parse_port() {
         let port_str = "80";
-        assert_eq!(Ports::from_str(&port_str), Ok(Ports::Port(80)))
+        assert_eq!(Ports::from_str(port_str), Ok(Ports::Port(80)))
     }
 }
 
-#[derive(Debug,Default, Eq, PartialEq)]
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
 pub struct PortFilter {
     port_map: BTreeMap<u16, u16>,
 }
 
 impl PortFilter {
 
-    pub fn insert_range(&mut self, start: u16, end: u16) {
+    //Overlapping ranges are merged, so that a port can only be within the last range
+    //starting before it. A range whose start is greater than its end is empty.
+    pub fn insert_range(&mut self, mut start: u16, mut end: u16) {
+        if start > end {
+            return;
+        }
+        if let Some((&first, &last)) = self.port_map.range(..=start).next_back() {
+            if last >= start {
+                start = first;
+                end = end.max(last);
+            }
+        }
+        let overlapped: Vec<u16> = self
+            .port_map
+            .range(start..=end)
+            .map(|(&first, _)| first)
+            .collect();
+        for first in overlapped {
+            if let Some(last) = self.port_map.remove(&first) {
+                end = end.max(last);
+            }
+        }
         self.port_map.insert(start, end);
     }
 
@@ -78,7 +105,7 @@ impl PortFilter {
     }
 
     pub fn insert_single(&mut self, port: u16) {
-        self.port_map.insert(port, port);
+        self.insert_range(port, port);
     }
 
     pub fn contains(&self, port: u16) -> bool {
@@ -101,17 +128,36 @@ mod test {
         let mut filter = PortFilter::default();
         filter.insert_single(443);
         filter.insert_single(80);
-        assert_eq!(filter.contains(443), true);
-        assert_eq!(filter.contains(80), true);
-        assert_eq!(filter.contains(22), false);
+        assert!(filter.contains(443));
+        assert!(filter.contains(80));
+        assert!(!filter.contains(22));
     }
     #[test]
     fn test_port_range() {
         let mut filter = PortFilter::default();
         filter.insert_range(80,443);
         for x in 80..=443 {
-        assert_eq!(filter.contains(x),true);
+        assert!(filter.contains(x));
         }
-        assert_eq!(filter.contains(67), false);
+        assert!(!filter.contains(67));
+    }
+    #[test]
+    fn test_overlapping_ranges() {
+        let mut filter = PortFilter::default();
+        filter.insert_range(1000, 2000);
+        filter.insert_range(1200, 1300);
+        filter.insert_single(1000);
+        assert!(filter.contains(1500));
+        assert!(filter.contains(2000));
+
+        filter.insert_range(1900, 2100);
+        filter.insert_range(500, 1000);
+        assert!(filter.contains(2100));
+        assert!(filter.contains(500));
+        assert!(!filter.contains(2101));
+        assert_eq!(filter.port_map, BTreeMap::from([(500, 2100)]));
+
+        filter.insert_range(443, 80);
+        assert!(!filter.contains(100));
     }
 }
@@ -0,0 +1,257 @@
+use std::{
+    fs::{self, File, Metadata},
+    io::{self, BufReader, BufWriter, Read, Write},
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use chrono::{DateTime, Local};
+use memmap2::Mmap;
+
+use crate::{ingesters::parser::LogParser, timestamp::TimeWindow, TimestampedLog};
+
+const INDEX_MAGIC: &[u8; 8] = b"FTIDX\0\0\x01";
+
+//Lines of a memory mapped log file, only their offset and timestamp are kept
+//in memory, the logs are parsed each time they are read.
+pub struct MappedLogs {
+    mmap: Arc<Mmap>,
+    parser: Arc<dyn LogParser>,
+    index: LineIndex,
+}
+
+impl MappedLogs {
+    pub fn new(mmap: Arc<Mmap>, parser: Arc<dyn LogParser>) -> Self {
+        Self {
+            mmap,
+            parser,
+            index: LineIndex::default(),
+        }
+    }
+
+    pub fn set_parser(&mut self, parser: Arc<dyn LogParser>) {
+        self.parser = parser;
+    }
+
+    pub fn index(&self) -> &LineIndex {
+        &self.index
+    }
+
+    pub fn append(&mut self, index: LineIndex) {
+        self.index.append(index);
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    pub fn line(&self, index: usize) -> Option<&str> {
+        let start = usize::try_from(self.index.offsets.get(index).copied()?).ok()?;
+        let rest = self.mmap.get(start..)?;
+        let line = match memchr::memchr(b'\n', rest) {
+            Some(end) => &rest[..end],
+            None => rest,
+        };
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        std::str::from_utf8(line).ok()
+    }
+
+    //Parses the line, None if only its header was valid.
+    pub fn get(&self, index: usize) -> Option<TimestampedLog> {
+        let mut log = self.parser.parse(self.line(index)?).ok()?;
+        //Lines without a timestamp keep the time they have been indexed at.
+        log.timestamp = self.index.timestamp(index)?;
+        Some(log)
+    }
+
+    pub fn timestamp(&self, index: usize) -> Option<DateTime<Local>> {
+        self.index.timestamp(index)
+    }
+}
+
+//Start offset and timestamp of each line of a file, 16 bytes per line.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct LineIndex {
+    offsets: Vec<u64>,
+    //Nanoseconds since the unix epoch.
+    timestamps: Vec<i64>,
+}
+
+impl LineIndex {
+    pub fn push(&mut self, offset: u64, timestamp: DateTime<Local>) {
+        self.offsets.push(offset);
+        self.timestamps
+            .push(timestamp.timestamp_nanos_opt().unwrap_or_default());
+    }
+
+    pub fn append(&mut self, mut other: LineIndex) {
+        self.offsets.append(&mut other.offsets);
+        self.timestamps.append(&mut other.timestamps);
+    }
+
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    //Keeps the lines within `window`.
+    pub fn within(self, window: &TimeWindow) -> Self {
+        let (offsets, timestamps) = self
+            .offsets
+            .into_iter()
+            .zip(self.timestamps)
+            .filter(|&(_, nanos)| {
+                window.contains(&DateTime::from_timestamp_nanos(nanos).with_timezone(&Local))
+            })
+            .unzip();
+        Self {
+            offsets,
+            timestamps,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    pub fn timestamp(&self, index: usize) -> Option<DateTime<Local>> {
+        let nanos = *self.timestamps.get(index)?;
+        Some(DateTime::from_timestamp_nanos(nanos).with_timezone(&Local))
+    }
+
+    //Where the index of `log_path` is cached.
+    pub fn cache_path(log_path: &Path) -> PathBuf {
+        let mut path = log_path.as_os_str().to_owned();
+        path.push(".ftidx");
+        PathBuf::from(path)
+    }
+
+    //The size and modification time of the indexed file are saved with the index,
+    //so that it is not reused once the file changes.
+    pub fn save(&self, path: &Path, indexed: &Metadata) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(INDEX_MAGIC)?;
+        for value in [
+            indexed.len(),
+            indexed.mtime() as u64,
+            indexed.mtime_nsec() as u64,
+            self.len() as u64,
+        ] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        for (offset, timestamp) in self.offsets.iter().zip(&self.timestamps) {
+            writer.write_all(&offset.to_le_bytes())?;
+            writer.write_all(&timestamp.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    //None when there is no cached index or it doesn't match the file anymore.
+    pub fn load(path: &Path, indexed: &Metadata) -> io::Result<Option<Self>> {
+        let mut reader = match File::open(path) {
+            Ok(file) => BufReader::new(file),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != INDEX_MAGIC
+            || read_u64(&mut reader)? != indexed.len()
+            || read_u64(&mut reader)? != indexed.mtime() as u64
+            || read_u64(&mut reader)? != indexed.mtime_nsec() as u64
+        {
+            return Ok(None);
+        }
+
+        let count = usize::try_from(read_u64(&mut reader)?).map_err(io::Error::other)?;
+        //The size is checked so that a corrupted count doesn't allocate too much.
+        let size = (count as u64)
+            .checked_mul(16)
+            .and_then(|size| size.checked_add(40));
+        if size != Some(fs::metadata(path)?.len()) {
+            return Ok(None);
+        }
+
+        let mut index = LineIndex {
+            offsets: Vec::with_capacity(count),
+            timestamps: Vec::with_capacity(count),
+        };
+        for _ in 0..count {
+            index.offsets.push(read_u64(&mut reader)?);
+            index.timestamps.push(read_u64(&mut reader)? as i64);
+        }
+        Ok(Some(index))
+    }
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingesters::{
+        parser::FilterlogParser,
+        tests::{syslog_line_at, TempDir, SYSLOG_LINE},
+    };
+
+    #[test]
+    fn test_index_cache() {
+        let dir = TempDir::new("index");
+        let log_path = dir.join("filter.log");
+        fs::write(&log_path, "first\nsecond\n").unwrap();
+        let cache_path = LineIndex::cache_path(&log_path);
+        assert_eq!(cache_path, dir.join("filter.log.ftidx"));
+
+        let mut index = LineIndex::default();
+        index.push(0, Local::now());
+        index.push(6, DateTime::UNIX_EPOCH.with_timezone(&Local));
+
+        let metadata = fs::metadata(&log_path).unwrap();
+        index.save(&cache_path, &metadata).unwrap();
+        assert_eq!(
+            LineIndex::load(&cache_path, &metadata).unwrap(),
+            Some(index)
+        );
+
+        //The file has grown since it has been indexed.
+        fs::write(&log_path, "first\nsecond\nthird\n").unwrap();
+        let metadata = fs::metadata(&log_path).unwrap();
+        assert_eq!(LineIndex::load(&cache_path, &metadata).unwrap(), None);
+
+        //Corrupted count.
+        LineIndex::default().save(&cache_path, &metadata).unwrap();
+        let mut cache = fs::read(&cache_path).unwrap();
+        cache[32..40].copy_from_slice(&(u64::MAX / 4).to_le_bytes());
+        fs::write(&cache_path, cache).unwrap();
+        assert_eq!(LineIndex::load(&cache_path, &metadata).unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_on_read() {
+        let dir = TempDir::new("mapped");
+        let path = dir.join("filter.log");
+        fs::write(&path, format!("{}\r\n{}", SYSLOG_LINE, syslog_line_at("-"))).unwrap();
+
+        let mmap = unsafe { Mmap::map(&File::open(&path).unwrap()).unwrap() };
+        let mut logs = MappedLogs::new(Arc::new(mmap), Arc::new(FilterlogParser::default()));
+        let indexed_at = DateTime::UNIX_EPOCH.with_timezone(&Local);
+        let mut index = LineIndex::default();
+        index.push(0, Local::now());
+        index.push(SYSLOG_LINE.len() as u64 + 2, indexed_at);
+        logs.append(index);
+
+        assert_eq!(logs.line(0), Some(SYSLOG_LINE));
+        assert_eq!(logs.get(0).unwrap().log.packet_filter.interface, "vlan0.20");
+        assert_eq!(logs.get(1).unwrap().timestamp, indexed_at);
+        assert!(logs.get(2).is_none());
+    }
+}
@@ -0,0 +1,324 @@
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    mem::size_of,
+    ops::{Index, Range},
+};
+
+use chrono::{DateTime, Local};
+use senpa::{ip::IpSpecific, ProtoInfo};
+
+use crate::TimestampedLog;
+
+use mapped::MappedLogs;
+
+pub mod mapped;
+
+//Append only sequence whose oldest items can be dropped.
+//Items keep their absolute position, so positions held elsewhere either stay valid
+//or fall before `first`.
+#[derive(Debug)]
+pub struct Ring<T> {
+    items: VecDeque<T>,
+    first: usize,
+}
+
+impl<T> Default for Ring<T> {
+    fn default() -> Self {
+        Self {
+            items: VecDeque::new(),
+            first: 0,
+        }
+    }
+}
+
+impl<T> Ring<T> {
+    pub fn push(&mut self, item: T) {
+        self.items.push_back(item);
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let item = self.items.pop_front()?;
+        self.first += 1;
+        Some(item)
+    }
+
+    //Position of the oldest item kept.
+    pub fn first(&self) -> usize {
+        self.first
+    }
+
+    //Position the next item will be pushed at.
+    pub fn end(&self) -> usize {
+        self.first + self.items.len()
+    }
+
+    //Number of items kept.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.items.front()
+    }
+
+    pub fn get(&self, position: usize) -> Option<&T> {
+        self.items.get(position.checked_sub(self.first)?)
+    }
+
+    //Items in `range`, positions already dropped are skipped.
+    pub fn range(&self, range: Range<usize>) -> impl Iterator<Item = &T> {
+        let start = range.start.clamp(self.first, self.end()) - self.first;
+        let end = range.end.clamp(self.first, self.end()) - self.first;
+        self.items.range(start..end.max(start))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.items.iter()
+    }
+
+    //Position of the first item for which `pred` is false, like `slice::partition_point`.
+    pub fn partition_point<P: FnMut(&T) -> bool>(&self, pred: P) -> usize {
+        self.first + self.items.partition_point(pred)
+    }
+}
+
+impl<T> Index<usize> for Ring<T> {
+    type Output = T;
+
+    fn index(&self, position: usize) -> &T {
+        &self.items[position - self.first]
+    }
+}
+
+//Every log read so far, bounded by the optional limits: once one is exceeded the oldest
+//logs are evicted. Logs are addressed by absolute index, see `Ring`.
+#[derive(Default)]
+pub struct LogStore {
+    storage: Storage,
+    max_logs: Option<usize>,
+    max_memory: Option<usize>,
+    //Estimated size of the logs kept, in bytes.
+    memory: usize,
+}
+
+enum Storage {
+    Parsed(Ring<TimestampedLog>),
+    //Logs of a memory mapped file, they are never evicted.
+    Mapped(MappedLogs),
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Storage::Parsed(Ring::default())
+    }
+}
+
+impl LogStore {
+    pub fn mapped(logs: MappedLogs) -> Self {
+        Self {
+            storage: Storage::Mapped(logs),
+            ..Default::default()
+        }
+    }
+
+    pub fn mapped_logs(&self) -> Option<&MappedLogs> {
+        match &self.storage {
+            Storage::Mapped(logs) => Some(logs),
+            Storage::Parsed(_) => None,
+        }
+    }
+
+    pub fn mapped_logs_mut(&mut self) -> Option<&mut MappedLogs> {
+        match &mut self.storage {
+            Storage::Mapped(logs) => Some(logs),
+            Storage::Parsed(_) => None,
+        }
+    }
+
+    pub fn set_limits(&mut self, max_logs: Option<usize>, max_memory: Option<usize>) {
+        self.max_logs = max_logs;
+        self.max_memory = max_memory;
+        self.evict();
+    }
+
+    //Mapped logs are only read from their file, see `mapped_logs_mut`.
+    pub fn push(&mut self, log: TimestampedLog) {
+        if let Storage::Parsed(logs) = &mut self.storage {
+            self.memory += log_size(&log);
+            logs.push(log);
+            self.evict();
+        }
+    }
+
+    fn evict(&mut self) {
+        let Storage::Parsed(logs) = &mut self.storage else {
+            return;
+        };
+
+        loop {
+            let count = logs.items.len();
+            let over_limits = self.max_logs.is_some_and(|max| count > max)
+                || self.max_memory.is_some_and(|max| self.memory > max);
+            if !over_limits {
+                break;
+            }
+
+            let Some(log) = logs.pop_front() else {
+                break;
+            };
+            self.memory -= log_size(&log);
+        }
+    }
+
+    //Index of the oldest log kept.
+    pub fn first(&self) -> usize {
+        match &self.storage {
+            Storage::Parsed(logs) => logs.first(),
+            Storage::Mapped(_) => 0,
+        }
+    }
+
+    //Index the next log will be stored at.
+    pub fn end(&self) -> usize {
+        match &self.storage {
+            Storage::Parsed(logs) => logs.end(),
+            Storage::Mapped(logs) => logs.len(),
+        }
+    }
+
+    //Number of logs kept.
+    pub fn len(&self) -> usize {
+        self.end() - self.first()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.first() == self.end()
+    }
+
+    //Mapped logs are parsed on each call, None when the line turns out to be invalid.
+    pub fn get(&self, index: usize) -> Option<Cow<'_, TimestampedLog>> {
+        match &self.storage {
+            Storage::Parsed(logs) => logs.get(index).map(Cow::Borrowed),
+            Storage::Mapped(logs) => logs.get(index).map(Cow::Owned),
+        }
+    }
+
+    //Timestamp of a log, mapped logs are not parsed to get it.
+    pub fn timestamp(&self, index: usize) -> Option<DateTime<Local>> {
+        match &self.storage {
+            Storage::Parsed(logs) => logs.get(index).map(|log| log.timestamp),
+            Storage::Mapped(logs) => logs.timestamp(index),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Cow<'_, TimestampedLog>> {
+        (self.first()..self.end()).filter_map(|index| self.get(index))
+    }
+
+    //Position of the first filtered index whose log is still stored.
+    pub fn first_position(&self, filtered_logs: &Ring<usize>) -> usize {
+        filtered_logs.partition_point(|&index| index < self.first())
+    }
+}
+
+//Estimated memory used by a log, including the strings it owns.
+fn log_size(log: &TimestampedLog) -> usize {
+    let header = &log.header;
+    let reason = &log.reason;
+    let log = &log.log;
+    let rule_info = &log.packet_filter.rule_info;
+
+    let mut size = size_of::<TimestampedLog>()
+        + [&header.hostname, &header.app_name, &header.proc_id]
+            .iter()
+            .map(|field| field.as_ref().map_or(0, String::capacity))
+            .sum::<usize>()
+        + reason.capacity()
+        + log.packet_filter.interface.capacity()
+        + rule_info.label.capacity()
+        + rule_info.anchorname.as_ref().map_or(0, String::capacity);
+
+    size += match &log.ip_specific {
+        IpSpecific::IpV4(ipv4) => {
+            ipv4.flags.capacity() + ipv4.ecn.as_ref().map_or(0, String::capacity)
+        }
+        IpSpecific::Ipv6(ipv6) => ipv6.flow_label.capacity(),
+    };
+
+    size += match &log.proto_info {
+        ProtoInfo::TcpInfo(tcp) => {
+            tcp.flags.capacity() + tcp.sequence_number.capacity() + tcp.options.capacity()
+        }
+        ProtoInfo::UdpInfo(_) => 0,
+        ProtoInfo::UnknownInfo(info) => info.capacity(),
+    };
+
+    size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingesters::{
+        parser::{FilterlogParser, LogParser},
+        tests::SYSLOG_LINE,
+    };
+
+    fn log() -> TimestampedLog {
+        FilterlogParser::default().parse(SYSLOG_LINE).unwrap()
+    }
+
+    #[test]
+    fn test_ring_positions() {
+        let mut ring = Ring::default();
+        for item in 0..5 {
+            ring.push(item);
+        }
+        ring.pop_front();
+        ring.pop_front();
+
+        assert_eq!(ring.first(), 2);
+        assert_eq!(ring.end(), 5);
+        assert_eq!(ring.len(), 3);
+        assert_eq!(ring.get(1), None);
+        assert_eq!(ring[3], 3);
+        assert_eq!(ring.range(0..4).collect::<Vec<_>>(), [&2, &3]);
+        assert_eq!(ring.range(4..9).collect::<Vec<_>>(), [&4]);
+        assert_eq!(ring.range(7..9).count(), 0);
+        assert_eq!(ring.partition_point(|&item| item < 4), 4);
+    }
+
+    #[test]
+    fn test_max_logs() {
+        let mut store = LogStore::default();
+        store.set_limits(Some(2), None);
+        for _ in 0..5 {
+            store.push(log());
+        }
+
+        assert_eq!(store.first(), 3);
+        assert_eq!(store.end(), 5);
+        assert_eq!(store.len(), 2);
+        assert!(store.get(2).is_none());
+        assert!(store.get(4).is_some());
+    }
+
+    #[test]
+    fn test_max_memory() {
+        let size = log_size(&log());
+        let mut store = LogStore::default();
+        for _ in 0..10 {
+            store.push(log());
+        }
+
+        store.set_limits(None, Some(size * 3));
+        assert_eq!(store.iter().count(), 3);
+        assert_eq!(store.first(), 7);
+        assert_eq!(store.memory, size * 3);
+    }
+}
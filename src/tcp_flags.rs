@@ -0,0 +1,92 @@
+use std::str::FromStr;
+
+//Letters used by pf for the tcp flags, in the order of their bits.
+const FLAGS: &str = "FSRPAUEW";
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct ParseTcpFlagsError;
+
+//Tcp flags checked like the pf `flags` option: `S/SA` matches the packets with SYN set
+//and ACK unset, the other flags are ignored. Without a mask, e.g. `SA`, only the given
+//flags are checked, all of them must be set.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct TcpFlags {
+    set: u8,
+    mask: u8,
+}
+
+impl TcpFlags {
+    //Flags of a log, unknown letters are ignored.
+    fn bits(flags: &str) -> u8 {
+        flags
+            .chars()
+            .filter_map(|c| FLAGS.find(c.to_ascii_uppercase()))
+            .fold(0, |bits, bit| bits | 1 << bit)
+    }
+
+    pub fn matches(&self, flags: &str) -> bool {
+        Self::bits(flags) & self.mask == self.set
+    }
+}
+
+impl FromStr for TcpFlags {
+    type Err = ParseTcpFlagsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |flags: &str| {
+            flags.chars().try_fold(0u8, |bits, c| {
+                let bit = FLAGS
+                    .find(c.to_ascii_uppercase())
+                    .ok_or(ParseTcpFlagsError)?;
+                Ok(bits | 1 << bit)
+            })
+        };
+
+        let (set, mask) = match s.split_once('/') {
+            Some((set, mask)) => (parse(set)?, parse(mask)?),
+            None => (parse(s)?, parse(s)?),
+        };
+        if s.is_empty() || mask == 0 || set & !mask != 0 {
+            return Err(ParseTcpFlagsError);
+        }
+        Ok(Self { set, mask })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tcp_flags() {
+        assert_eq!(
+            TcpFlags::from_str("S/SA"),
+            Ok(TcpFlags {
+                set: 0b10,
+                mask: 0b10010
+            })
+        );
+        assert_eq!(TcpFlags::from_str("sa"), TcpFlags::from_str("SA/AS"));
+        assert_eq!(TcpFlags::from_str(""), Err(ParseTcpFlagsError));
+        assert_eq!(TcpFlags::from_str("S/"), Err(ParseTcpFlagsError));
+        assert_eq!(TcpFlags::from_str("SA/S"), Err(ParseTcpFlagsError));
+        assert_eq!(TcpFlags::from_str("SX"), Err(ParseTcpFlagsError));
+    }
+
+    #[test]
+    fn test_match_tcp_flags() {
+        let syn = TcpFlags::from_str("S/SA").unwrap();
+        assert!(syn.matches("S"));
+        assert!(syn.matches("SEW"));
+        assert!(!syn.matches("SA"));
+        assert!(!syn.matches("PA"));
+
+        //No flag among FIN, SYN, RST and ACK, as sent by null and xmas scans.
+        let no_handshake = TcpFlags::from_str("/FSRA").unwrap();
+        assert!(no_handshake.matches(""));
+        assert!(no_handshake.matches("PU"));
+        assert!(!no_handshake.matches("FPU"));
+
+        assert!(TcpFlags::from_str("FA").unwrap().matches("FPA"));
+    }
+}
@@ -0,0 +1,384 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone,
+};
+use chrono_tz::Tz;
+
+//How the timestamp of a log has been obtained.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TimestampSource {
+    //RFC 5424 / RFC 3339 timestamp, as sent, with its offset when it had one.
+    Rfc5424(Option<FixedOffset>),
+    //RFC 3164 timestamp, the year is not part of it and has been inferred.
+    Rfc3164,
+    //Seconds (or milliseconds) since the unix epoch.
+    Epoch,
+    //The message has no timestamp, the time it has been received is used instead.
+    Received,
+}
+
+//Time range of the logs kept, both bounds are included.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct TimeWindow {
+    pub since: Option<DateTime<Local>>,
+    pub until: Option<DateTime<Local>>,
+}
+
+impl TimeWindow {
+    pub fn is_unbounded(&self) -> bool {
+        self.since.is_none() && self.until.is_none()
+    }
+
+    pub fn contains(&self, timestamp: &DateTime<Local>) -> bool {
+        self.since.is_none_or(|since| *timestamp >= since) && !self.is_past(timestamp)
+    }
+
+    //In a sorted input, every log following this one is outside the window too.
+    pub fn is_past(&self, timestamp: &DateTime<Local>) -> bool {
+        self.until.is_some_and(|until| *timestamp > until)
+    }
+}
+
+//Timezone timestamps are shown in and typed times are read in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayZone {
+    #[default]
+    Local,
+    Named(Tz),
+}
+
+impl DisplayZone {
+    pub fn format(&self, timestamp: &DateTime<Local>, format: &str) -> String {
+        match self {
+            DisplayZone::Local => timestamp.format(format).to_string(),
+            DisplayZone::Named(tz) => timestamp.with_timezone(tz).format(format).to_string(),
+        }
+    }
+
+    pub fn date_naive(&self, timestamp: &DateTime<Local>) -> NaiveDate {
+        match self {
+            DisplayZone::Local => timestamp.date_naive(),
+            DisplayZone::Named(tz) => timestamp.with_timezone(tz).date_naive(),
+        }
+    }
+
+    //Reads a time typed in this zone, the earliest one when it is ambiguous.
+    pub fn from_naive(&self, naive: &NaiveDateTime) -> Option<DateTime<Local>> {
+        match self {
+            DisplayZone::Local => Local.from_local_datetime(naive).earliest(),
+            DisplayZone::Named(tz) => tz
+                .from_local_datetime(naive)
+                .earliest()
+                .map(|datetime| datetime.with_timezone(&Local)),
+        }
+    }
+}
+
+impl FromStr for DisplayZone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("local") {
+            return Ok(DisplayZone::Local);
+        }
+        if s.eq_ignore_ascii_case("utc") {
+            return Ok(DisplayZone::Named(Tz::UTC));
+        }
+        s.parse()
+            .map(DisplayZone::Named)
+            .map_err(|_| format!("unknown timezone: {}", s))
+    }
+}
+
+impl fmt::Display for DisplayZone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DisplayZone::Local => write!(f, "local"),
+            DisplayZone::Named(tz) => write!(f, "{}", tz.name()),
+        }
+    }
+}
+
+//Parses a duration like `30m` (s, m, h, d or w).
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let unit_start = value.char_indices().last().map_or(0, |(index, _)| index);
+    let (amount, unit) = value.split_at(unit_start);
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| format!("invalid duration: {}", value))?;
+    let duration = match unit {
+        "s" => Duration::try_seconds(amount),
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => return Err(format!("invalid duration unit: {}", value)),
+    };
+    duration.ok_or_else(|| format!("invalid duration: {}", value))
+}
+
+//Parses a bound of a time window: a duration before `now` like `-2h` (s, m, h, d or w),
+//a date with an optional time like `2025-01-02 10:00` read in `zone`, or any timestamp
+//found in logs.
+pub fn parse_time_bound(
+    value: &str,
+    now: DateTime<Local>,
+    zone: &DisplayZone,
+) -> Result<DateTime<Local>, String> {
+    let value = value.trim();
+
+    if let Some(relative) = value.strip_prefix('-') {
+        let duration = parse_duration(relative)?;
+        return now
+            .checked_sub_signed(duration)
+            .ok_or_else(|| format!("invalid duration: {}", value));
+    }
+
+    let naive = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+            date.and_hms_opt(0, 0, 0)
+        });
+    if let Some(naive) = naive {
+        return zone
+            .from_naive(&naive)
+            .ok_or_else(|| format!("nonexistent time in {}: {}", zone, value));
+    }
+
+    parse_timestamp(value, now)
+        .map(|(datetime, _)| datetime)
+        .ok_or_else(|| format!("invalid time: {}", value))
+}
+
+//Parses a syslog timestamp in any of the supported formats.
+//`now` is used to infer the year of RFC 3164 timestamps.
+pub fn parse_timestamp(
+    timestamp: &str,
+    now: DateTime<Local>,
+) -> Option<(DateTime<Local>, TimestampSource)> {
+    let timestamp = timestamp.trim();
+
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(timestamp) {
+        return Some((
+            datetime.with_timezone(&Local),
+            TimestampSource::Rfc5424(Some(*datetime.offset())),
+        ));
+    }
+
+    //RFC 5424 timestamp without offset, seen from some relays.
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%dT%H:%M:%S%.f") {
+        return Some((
+            Local.from_local_datetime(&datetime).earliest()?,
+            TimestampSource::Rfc5424(None),
+        ));
+    }
+
+    if let Some(datetime) = parse_epoch(timestamp) {
+        return Some((datetime, TimestampSource::Epoch));
+    }
+
+    parse_rfc3164(timestamp, now).map(|datetime| (datetime, TimestampSource::Rfc3164))
+}
+
+fn parse_epoch(timestamp: &str) -> Option<DateTime<Local>> {
+    let (secs, fraction) = timestamp.split_once('.').unwrap_or((timestamp, ""));
+    if secs.is_empty()
+        || !secs.bytes().all(|b| b.is_ascii_digit())
+        || !fraction.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let secs: i64 = secs.parse().ok()?;
+    let nanos: u32 = format!("{:0<9}", fraction).get(..9)?.parse().ok()?;
+
+    //13 digits are milliseconds, seconds won't get there for a long time.
+    let datetime = if secs >= 100_000_000_000 && fraction.is_empty() {
+        DateTime::from_timestamp_millis(secs)?
+    } else {
+        DateTime::from_timestamp(secs, nanos)?
+    };
+
+    Some(datetime.with_timezone(&Local))
+}
+
+//Parses `Jan  2 12:00:00`. The year is the one that puts the timestamp closest
+//to `now` without being in the future, so december logs read in january work.
+fn parse_rfc3164(timestamp: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    //The latest year that makes a valid date not in the future, leaving some room for
+    //clock skew between the firewall and this host. Feb 29 can be up to 8 years back.
+    (0..=8).find_map(|years_ago| {
+        let year = now.year() - years_ago;
+        let datetime =
+            NaiveDateTime::parse_from_str(&format!("{} {}", year, timestamp), "%Y %b %e %H:%M:%S")
+                .ok()?;
+        let datetime = Local.from_local_datetime(&datetime).earliest()?;
+        (datetime <= now + Duration::days(1)).then_some(datetime)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(y: i32, m: u32, d: u32, h: u32, min: u32, s: u32) -> DateTime<Local> {
+        Local
+            .from_local_datetime(
+                &NaiveDate::from_ymd_opt(y, m, d)
+                    .unwrap()
+                    .and_hms_opt(h, min, s)
+                    .unwrap(),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_rfc5424() {
+        let now = Local::now();
+        let (datetime, source) = parse_timestamp("2025-01-02T12:00:00.123+01:00", now).unwrap();
+        assert_eq!(
+            source,
+            TimestampSource::Rfc5424(FixedOffset::east_opt(3600))
+        );
+        assert_eq!(
+            datetime,
+            DateTime::parse_from_rfc3339("2025-01-02T11:00:00.123Z").unwrap()
+        );
+
+        let (datetime, source) = parse_timestamp("2025-01-02T12:00:00", now).unwrap();
+        assert_eq!(source, TimestampSource::Rfc5424(None));
+        assert_eq!(datetime, local(2025, 1, 2, 12, 0, 0));
+    }
+
+    #[test]
+    fn test_rfc3164_year_inference() {
+        let now = local(2025, 6, 15, 10, 0, 0);
+        assert_eq!(
+            parse_timestamp("Jan  2 12:00:00", now),
+            Some((local(2025, 1, 2, 12, 0, 0), TimestampSource::Rfc3164))
+        );
+        assert_eq!(
+            parse_timestamp("Jun 16 09:00:00", now),
+            Some((local(2025, 6, 16, 9, 0, 0), TimestampSource::Rfc3164))
+        );
+
+        //December logs read in january belong to the previous year.
+        let now = local(2025, 1, 1, 0, 10, 0);
+        assert_eq!(
+            parse_timestamp("Dec 31 23:59:59", now),
+            Some((local(2024, 12, 31, 23, 59, 59), TimestampSource::Rfc3164))
+        );
+
+        //Feb 29 belongs to the last leap year.
+        let now = local(2025, 6, 15, 10, 0, 0);
+        assert_eq!(
+            parse_timestamp("Feb 29 12:00:00", now),
+            Some((local(2024, 2, 29, 12, 0, 0), TimestampSource::Rfc3164))
+        );
+        assert_eq!(parse_timestamp("Feb 30 12:00:00", now), None);
+    }
+
+    #[test]
+    fn test_epoch() {
+        let now = Local::now();
+        let expected = DateTime::parse_from_rfc3339("2025-01-02T11:00:00Z").unwrap();
+        assert_eq!(
+            parse_timestamp("1735815600", now),
+            Some((expected.with_timezone(&Local), TimestampSource::Epoch))
+        );
+        assert_eq!(
+            parse_timestamp("1735815600000", now),
+            Some((expected.with_timezone(&Local), TimestampSource::Epoch))
+        );
+        let (datetime, _) = parse_timestamp("1735815600.5", now).unwrap();
+        assert_eq!(datetime.timestamp_subsec_millis(), 500);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let now = Local::now();
+        assert_eq!(parse_timestamp("yesterday", now), None);
+        assert_eq!(parse_timestamp("", now), None);
+        assert_eq!(parse_timestamp("Foo  2 12:00:00", now), None);
+    }
+
+    #[test]
+    fn test_time_bound() {
+        let now = local(2025, 1, 2, 12, 0, 0);
+        let zone = DisplayZone::Local;
+        assert_eq!(
+            parse_time_bound("-2h", now, &zone),
+            Ok(local(2025, 1, 2, 10, 0, 0))
+        );
+        assert_eq!(
+            parse_time_bound("-1d", now, &zone),
+            Ok(local(2025, 1, 1, 12, 0, 0))
+        );
+        assert_eq!(
+            parse_time_bound("2025-01-02 10:30", now, &zone),
+            Ok(local(2025, 1, 2, 10, 30, 0))
+        );
+        assert_eq!(
+            parse_time_bound("2025-01-01", now, &zone),
+            Ok(local(2025, 1, 1, 0, 0, 0))
+        );
+        assert_eq!(
+            parse_time_bound("2025-01-02T11:00:00Z", now, &zone),
+            Ok(DateTime::parse_from_rfc3339("2025-01-02T11:00:00Z")
+                .unwrap()
+                .with_timezone(&Local))
+        );
+        assert_eq!(
+            parse_time_bound("2025-01-02 10:30", now, &"UTC".parse().unwrap()),
+            Ok(DateTime::parse_from_rfc3339("2025-01-02T10:30:00Z")
+                .unwrap()
+                .with_timezone(&Local))
+        );
+        assert!(parse_time_bound("-2y", now, &zone).is_err());
+        assert!(parse_time_bound("-h", now, &zone).is_err());
+        assert!(parse_time_bound("-2é", now, &zone).is_err());
+        assert!(parse_time_bound("yesterday", now, &zone).is_err());
+    }
+
+    #[test]
+    fn test_time_window() {
+        let window = TimeWindow {
+            since: Some(local(2025, 1, 2, 10, 0, 0)),
+            until: Some(local(2025, 1, 2, 11, 0, 0)),
+        };
+        assert!(window.contains(&local(2025, 1, 2, 10, 0, 0)));
+        assert!(window.contains(&local(2025, 1, 2, 11, 0, 0)));
+        assert!(!window.contains(&local(2025, 1, 2, 9, 59, 59)));
+        assert!(!window.is_past(&local(2025, 1, 2, 9, 0, 0)));
+        assert!(window.is_past(&local(2025, 1, 2, 11, 0, 1)));
+        assert!(TimeWindow::default().contains(&local(2025, 1, 2, 9, 0, 0)));
+    }
+
+    #[test]
+    fn test_display_zone() {
+        let zone: DisplayZone = "Asia/Tokyo".parse().unwrap();
+        let timestamp = DateTime::parse_from_rfc3339("2025-01-02T23:30:00Z")
+            .unwrap()
+            .with_timezone(&Local);
+        assert_eq!(
+            zone.format(&timestamp, "%Y-%m-%d %H:%M:%S"),
+            "2025-01-03 08:30:00"
+        );
+        assert_eq!(
+            zone.date_naive(&timestamp),
+            NaiveDate::from_ymd_opt(2025, 1, 3).unwrap()
+        );
+        let typed = NaiveDate::from_ymd_opt(2025, 1, 3)
+            .unwrap()
+            .and_hms_opt(8, 30, 0)
+            .unwrap();
+        assert_eq!(zone.from_naive(&typed), Some(timestamp));
+
+        assert_eq!("utc".parse(), Ok(DisplayZone::Named(Tz::UTC)));
+        assert_eq!("Local".parse(), Ok(DisplayZone::Local));
+        assert!("Mars/Olympus".parse::<DisplayZone>().is_err());
+    }
+}
@@ -0,0 +1,51 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Stylize,
+    text::{Line, Text},
+    widgets::{Block, Paragraph, Widget},
+};
+
+use crate::ingesters::command::CommandStatus;
+
+//Status of the command started with --exec.
+pub struct CommandStatusBar<'a> {
+    pub status: &'a CommandStatus,
+}
+
+impl<'a> CommandStatusBar<'a> {
+    pub fn new(status: &'a CommandStatus) -> Self {
+        Self { status }
+    }
+}
+
+impl Widget for CommandStatusBar<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut state = vec![if self.status.running {
+            " Running ".green().bold()
+        } else {
+            " Stopped ".red().bold()
+        }];
+
+        if let Some(last_exit) = &self.status.last_exit {
+            state.extend([" Last exit: ".bold(), last_exit.clone().into()]);
+        }
+        if self.status.restarts > 0 {
+            state.extend([
+                " Restarts: ".bold(),
+                self.status.restarts.to_string().into(),
+            ]);
+        }
+
+        let stderr = match self.status.stderr.back() {
+            Some(line) => Line::from(vec![" stderr: ".bold(), line.clone().gray()]),
+            None => Line::default(),
+        };
+
+        let block = Block::bordered().title(Line::from(format!(" exec: {} ", self.status.command)));
+
+        Paragraph::new(Text::from(vec![Line::from(state), stderr]))
+            .block(block)
+            .render(area, buf);
+    }
+}
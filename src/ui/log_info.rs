@@ -7,31 +7,67 @@ use ratatui::{
 };
 use senpa::{Action, Dir, ProtoInfo};
 
-use crate::TimestampedLog;
+use crate::{
+    timestamp::{DisplayZone, TimestampSource},
+    TimestampedLog,
+};
 
 pub struct LogInfoPopup {
     pub border_style: Style,
     pub title_style: Style,
     pub style: Style,
     pub timestamped_log: TimestampedLog,
+    pub zone: DisplayZone,
 }
 
 impl LogInfoPopup {
-    pub fn new(timestamped_log: TimestampedLog) -> Self {
+    pub fn new(timestamped_log: TimestampedLog, zone: DisplayZone) -> Self {
         Self {
             border_style: Style::default().yellow(),
             title_style: Style::default(),
             style: Style::default(),
             timestamped_log,
+            zone,
         }
     }
-    fn get_content(&self) -> Text {
+    fn get_content(&self) -> Text<'_> {
         let log = &self.timestamped_log.log;
+        let header = &self.timestamped_log.header;
+        let rule_info = &log.packet_filter.rule_info;
 
         let mut content = vec![
             Line::from(vec![
                 " Timestamp: ".bold(),
-                format!("{}", self.timestamped_log.timestamp).into(),
+                self.zone
+                    .format(&self.timestamped_log.timestamp, "%Y-%m-%d %H:%M:%S%.f %:z")
+                    .into(),
+                match self.timestamped_log.timestamp_source {
+                    TimestampSource::Rfc5424(Some(offset)) => {
+                        format!(" (sent as {})", offset).into()
+                    }
+                    TimestampSource::Rfc5424(None) => " (no offset, local time assumed)".yellow(),
+                    TimestampSource::Rfc3164 => " (year inferred)".yellow(),
+                    TimestampSource::Epoch => " (epoch)".into(),
+                    TimestampSource::Received => " (receive time)".yellow(),
+                },
+            ]),
+            Line::from(vec![
+                " Host: ".bold(),
+                header.hostname.as_deref().unwrap_or("-").to_string().into(),
+                " App: ".bold(),
+                match (&header.app_name, &header.proc_id) {
+                    (Some(app_name), Some(proc_id)) => format!("{}[{}]", app_name, proc_id),
+                    (Some(app_name), None) => app_name.clone(),
+                    (None, Some(proc_id)) => format!("-[{}]", proc_id),
+                    (None, None) => "-".into(),
+                }
+                .into(),
+            ]),
+            Line::from(vec![
+                " Facility: ".bold(),
+                header.facility_name().unwrap_or("-").into(),
+                " Severity: ".bold(),
+                header.severity_name().unwrap_or("-").into(),
             ]),
             Line::from(vec![
                 " Action: ".bold(),
@@ -40,6 +76,32 @@ impl LogInfoPopup {
                     Action::Reject => "Reject".green().bold(),
                     Action::Block => "Block".red().bold(),
                 },
+                " Reason: ".bold(),
+                self.timestamped_log.reason.as_str().into(),
+            ]),
+            Line::from(vec![
+                " Rule: ".bold(),
+                rule_info.number.to_string().into(),
+                " Sub-rule: ".bold(),
+                rule_info
+                    .subrulenr
+                    .map_or_else(|| "-".into(), |subrule| subrule.to_string())
+                    .into(),
+                " Anchor: ".bold(),
+                rule_info
+                    .anchorname
+                    .as_deref()
+                    .unwrap_or("-")
+                    .to_string()
+                    .into(),
+            ]),
+            Line::from(vec![
+                " Tracker: ".bold(),
+                match rule_info.label.as_str() {
+                    "" => "-".to_string(),
+                    label => label.to_string(),
+                }
+                .into(),
             ]),
             Line::from(vec![
                 " Interface: ".bold(),
@@ -148,7 +210,7 @@ impl Widget for LogInfoPopup {
     }
 }
 
-fn center(area: Rect, horizontal: Constraint, vertical: Constraint) -> Rect {
+pub fn center(area: Rect, horizontal: Constraint, vertical: Constraint) -> Rect {
     let [area] = Layout::horizontal([horizontal])
         .flex(Flex::Center)
         .areas(area);
@@ -8,29 +8,32 @@ use ratatui::{
     widgets::{Cell, Row, StatefulWidget, Table, TableState},
 };
 
-use senpa::{Action, ProtoName};
+use senpa::{packet_filter::RuleInfo, Action, ProtoName};
 
-use crate::TimestampedLog;
+use crate::{
+    store::{LogStore, Ring},
+    timestamp::DisplayZone,
+};
 
 pub struct LogTable {
-    pub logs: Arc<RwLock<Vec<TimestampedLog>>>,
+    pub logs: Arc<RwLock<LogStore>>,
     pub packet_window: usize,
+    //Positions in filtered_logs, absolute like the positions of the ring.
     pub start: usize,
     pub selected_packet: usize,
-    pub filtered_logs: Arc<RwLock<Vec<usize>>>,
+    pub filtered_logs: Arc<RwLock<Ring<usize>>>,
+    pub zone: DisplayZone,
 }
 
 impl LogTable {
-    pub fn new(
-        logs: Arc<RwLock<Vec<TimestampedLog>>>,
-        filtered_logs: Arc<RwLock<Vec<usize>>>,
-    ) -> Self {
+    pub fn new(logs: Arc<RwLock<LogStore>>, filtered_logs: Arc<RwLock<Ring<usize>>>) -> Self {
         Self {
             logs,
             packet_window: 0,
             start: 0,
             selected_packet: 0,
             filtered_logs,
+            zone: DisplayZone::default(),
         }
     }
 }
@@ -40,6 +43,13 @@ impl StatefulWidget for &mut LogTable {
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         let logs = self.logs.read().unwrap();
+        let filtered_logs = self.filtered_logs.read().unwrap();
+
+        //Evicted logs can't be shown anymore.
+        let first = logs.first_position(&filtered_logs);
+        self.start = self.start.max(first);
+        self.selected_packet = self.selected_packet.max(first);
+
         self.packet_window = area.height.saturating_sub(1).into();
         let mut last_log = self.start.saturating_add(self.packet_window);
 
@@ -50,29 +60,42 @@ impl StatefulWidget for &mut LogTable {
                 .min(self.selected_packet);
         }
 
-        let filtered_logs = self.filtered_logs.read().unwrap();
-
-        last_log = last_log.min(filtered_logs.len());
+        last_log = last_log.min(filtered_logs.end());
 
         state.select(Some(self.selected_packet.saturating_sub(self.start)));
 
-        let rows: Vec<_> = filtered_logs[self.start..last_log]
-            .iter()
+        let rows: Vec<_> = filtered_logs
+            .range(self.start..last_log)
             .map(|log_idx| {
-                let x = &logs[*log_idx];
+                //Only the header of mapped lines is checked before they are shown.
+                let Some(x) = logs.get(*log_idx) else {
+                    return Row::new([Cell::new(Text::from("invalid line").centered())])
+                        .style(Style::new().gray());
+                };
                 let mut cells = vec![
-                    Cell::new(Text::from(x.timestamp.naive_local().to_string()).centered()),
+                    Cell::new(
+                        Text::from(self.zone.format(&x.timestamp, "%Y-%m-%d %H:%M:%S%.f"))
+                            .centered(),
+                    ),
+                    Cell::new(
+                        Text::from(x.header.hostname.clone().unwrap_or_else(|| "-".into()))
+                            .centered(),
+                    ),
+                    Cell::new(Text::from(rule(&x.log.packet_filter.rule_info)).centered()),
                     Cell::new(Text::from(x.log.packet_filter.interface.clone()).centered()),
                     Cell::new(Text::from(x.log.ip_data.src.to_string()).centered()),
                     Cell::new(Text::from(x.log.ip_data.dst.to_string()).centered()),
                 ];
 
                 cells.push(Cell::new(
-                    Text::from(match &x.log.protocol.name {
-                        ProtoName::Tcp => "tcp",
-                        ProtoName::Udp => "udp",
-                        ProtoName::Other(other) => other,
-                    })
+                    Text::from(
+                        match &x.log.protocol.name {
+                            ProtoName::Tcp => "tcp",
+                            ProtoName::Udp => "udp",
+                            ProtoName::Other(other) => other,
+                        }
+                        .to_string(),
+                    )
                     .centered(),
                 ));
 
@@ -83,18 +106,49 @@ impl StatefulWidget for &mut LogTable {
             })
             .collect();
 
+        let time = match self.zone {
+            DisplayZone::Local => "Time".to_string(),
+            zone => format!("Time ({})", zone),
+        };
         let header = Row::new(
-            ["Time", "Interface", "Source", "Destination", "Proto"]
-                .iter()
-                .map(|&c| Cell::from(Text::from(c).alignment(Alignment::Center))),
+            [
+                &time,
+                "Host",
+                "Rule",
+                "Interface",
+                "Source",
+                "Destination",
+                "Proto",
+            ]
+            .iter()
+            .map(|&c| Cell::from(Text::from(c).alignment(Alignment::Center))),
         );
 
-        let table = Table::new(rows, [Constraint::Percentage(20); 5])
-            .header(header)
-            .flex(Flex::Center)
-            .highlight_symbol(">>")
-            .row_highlight_style(Style::new().on_gray());
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Percentage(18),
+                Constraint::Percentage(14),
+                Constraint::Percentage(8),
+                Constraint::Percentage(12),
+                Constraint::Percentage(19),
+                Constraint::Percentage(19),
+                Constraint::Percentage(10),
+            ],
+        )
+        .header(header)
+        .flex(Flex::Center)
+        .highlight_symbol(">>")
+        .row_highlight_style(Style::new().on_gray());
 
         StatefulWidget::render(table, area, buf, state);
     }
 }
+
+//Rule number, followed by the sub-rule when there is one.
+fn rule(rule_info: &RuleInfo) -> String {
+    match rule_info.subrulenr {
+        Some(subrule) => format!("{}.{}", rule_info.number, subrule),
+        None => rule_info.number.to_string(),
+    }
+}
@@ -1,10 +1,11 @@
 use crate::{
     action::{self},
+    store::{LogStore, Ring},
     ui::log_table::LogTable,
     TimestampedLog,
 };
 use action::LogViewAction::*;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Local};
 
 use ratatui::{
     layout::Rect,
@@ -13,7 +14,10 @@ use ratatui::{
     Frame,
 };
 
-use std::sync::{Arc, RwLock};
+use std::{
+    borrow::Cow,
+    sync::{Arc, RwLock},
+};
 #[derive(Default, PartialEq, Eq)]
 pub enum ScrollMode {
     #[default]
@@ -25,14 +29,11 @@ pub struct LogView {
     table_state: TableState,
     scroll_mode: ScrollMode,
     pub log_table: LogTable,
-    pub filtered_logs: Arc<RwLock<Vec<usize>>>,
+    pub filtered_logs: Arc<RwLock<Ring<usize>>>,
 }
 
 impl LogView {
-    pub fn new(
-        logs: Arc<RwLock<Vec<TimestampedLog>>>,
-        filtered_logs: Arc<RwLock<Vec<usize>>>,
-    ) -> Self {
+    pub fn new(logs: Arc<RwLock<LogStore>>, filtered_logs: Arc<RwLock<Ring<usize>>>) -> Self {
         Self {
             table_state: TableState::new(),
             scrollbar_state: ScrollbarState::default(),
@@ -56,7 +57,6 @@ impl LogView {
             self.log_table.start = self.log_table.start.saturating_sub(1);
         }
         self.log_table.selected_packet = self.log_table.selected_packet.saturating_sub(1);
-        self.scrollbar_state.prev();
         self.table_state.scroll_up_by(1);
         if self.scroll_mode == ScrollMode::Auto {
             self.scroll_mode = ScrollMode::Manual;
@@ -64,17 +64,16 @@ impl LogView {
     }
 
     fn scroll_down(&mut self) {
-        let log_len = self.filtered_logs.read().unwrap().len();
+        let end = self.filtered_logs.read().unwrap().end();
 
         if self.table_state.selected() == Some(self.log_table.packet_window.saturating_sub(1)) {
             self.log_table.start += 1;
-            self.log_table.start = self.log_table.start.min(log_len.saturating_sub(1))
+            self.log_table.start = self.log_table.start.min(end.saturating_sub(1))
         }
 
         self.log_table.selected_packet =
-            (self.log_table.selected_packet + 1).min(log_len.saturating_sub(1));
+            (self.log_table.selected_packet + 1).min(end.saturating_sub(1));
 
-        self.scrollbar_state.next();
         self.table_state.scroll_down_by(1);
 
         if self.scroll_mode == ScrollMode::Auto {
@@ -83,21 +82,43 @@ impl LogView {
     }
 
     pub fn scroll_to_end(&mut self) {
-        let end = self.filtered_logs.read().unwrap().len().saturating_sub(1);
-        self.scrollbar_state = self.scrollbar_state.position(end);
-
+        let end = self.filtered_logs.read().unwrap().end().saturating_sub(1);
         self.log_table.selected_packet = end;
     }
 
     pub fn select_log(&mut self, index: usize) {
         self.log_table.selected_packet = index;
         self.log_table.start = index;
-        self.scrollbar_state = self.scrollbar_state.position(index);
         if self.scroll_mode == ScrollMode::Auto {
             self.scroll_mode = ScrollMode::Manual;
         }
     }
 
+    //Selects the log closest to the log at `index` in the store, keeping its row on screen.
+    //Used once filtered_logs was replaced by a new filter.
+    pub fn select_nearest_log(&mut self, index: usize) {
+        let row = self
+            .log_table
+            .selected_packet
+            .saturating_sub(self.log_table.start);
+        let position = {
+            let filtered_logs = self.filtered_logs.read().unwrap();
+            let (first, end) = (filtered_logs.first(), filtered_logs.end());
+            let upper = filtered_logs.partition_point(|&i| i < index);
+            if upper == first || end == first {
+                first
+            } else if upper == end
+                || index - filtered_logs[upper - 1] <= filtered_logs[upper] - index
+            {
+                upper - 1
+            } else {
+                upper
+            }
+        };
+        self.log_table.selected_packet = position;
+        self.log_table.start = position.saturating_sub(row);
+    }
+
     pub fn get_selected_log(&self) -> Option<TimestampedLog> {
         let log_index = {
             let filtered_logs = self.filtered_logs.read().ok()?;
@@ -105,52 +126,52 @@ impl LogView {
         };
 
         let logs = self.log_table.logs.read().ok()?;
-        logs.get(log_index).cloned()
+        logs.get(log_index).map(Cow::into_owned)
     }
 
-    pub fn select_closest_date(&mut self, date: chrono::NaiveDateTime) {
+    //Selects the first log with the timestamp closest to `date`, logs are sorted by timestamp.
+    pub fn select_closest_date(&mut self, date: DateTime<Local>) {
         let all_logs = self.log_table.logs.read().unwrap();
         let filtered_logs = self.filtered_logs.read().unwrap();
 
-        let search_result = filtered_logs
-            .binary_search_by(|index| all_logs[*index].timestamp.naive_local().cmp(&date));
-        let filt_log_len = filtered_logs.len();
-
-        drop(all_logs);
-        drop(filtered_logs);
-
-        let index = match search_result {
-            Ok(index) => self.find_first_occurrence(&index),
-            Err(index) => index,
+        let first = all_logs.first_position(&filtered_logs);
+        let end = filtered_logs.end();
+        if first >= end {
+            return;
         }
-        .min(filt_log_len - 1);
 
-        let mut index = self.find_first_occurrence(&index);
-
-        let lower = index.saturating_sub(1);
-        let lower_date = self.get_log_timestamp(lower);
+        //Mapped logs don't need to be parsed to get their timestamp.
+        let timestamp = |position: usize| {
+            all_logs
+                .timestamp(filtered_logs[position])
+                .unwrap_or_default()
+        };
+        //Position of the first log not older than `date`, evicted logs count as older.
+        let first_not_older = |date: DateTime<Local>| {
+            filtered_logs.partition_point(|&index| {
+                all_logs
+                    .timestamp(index)
+                    .is_none_or(|timestamp| timestamp < date)
+            })
+        };
 
-        if date.signed_duration_since(lower_date).abs()
-            < date
-                .signed_duration_since(self.get_log_timestamp(index))
-                .abs()
-        {
-            index = self.find_first_occurrence(&lower);
+        let upper = first_not_older(date).clamp(first, end - 1);
+        let mut closest = timestamp(upper);
+        if upper > first {
+            let lower = timestamp(upper - 1);
+            if date.signed_duration_since(lower).abs() < date.signed_duration_since(closest).abs() {
+                closest = lower;
+            }
         }
+        let index = first_not_older(closest).max(first);
 
-        self.select_log(index.min(filt_log_len - 1));
-    }
+        drop(all_logs);
+        drop(filtered_logs);
 
-    fn get_log_timestamp(&self, index: usize) -> NaiveDateTime {
-        let filtered_logs = self.filtered_logs.read().unwrap();
-        let all_logs = self.log_table.logs.read().unwrap();
-        all_logs[filtered_logs[index]].timestamp.naive_local()
+        self.select_log(index);
     }
 
     pub fn draw(&mut self, frame: &mut Frame, area: Rect) {
-        self.scrollbar_state = self
-            .scrollbar_state
-            .content_length(self.filtered_logs.read().unwrap().len());
         if self.scroll_mode == ScrollMode::Auto {
             self.scroll_to_end();
         }
@@ -164,6 +185,17 @@ impl LogView {
 
         frame.render_stateful_widget(&mut self.log_table, inner_area, &mut self.table_state);
 
+        //Positions are absolute, the scrollbar only covers the logs still stored.
+        let first = {
+            let logs = self.log_table.logs.read().unwrap();
+            logs.first_position(&self.filtered_logs.read().unwrap())
+        };
+        let end = self.filtered_logs.read().unwrap().end();
+        self.scrollbar_state = self
+            .scrollbar_state
+            .content_length(end - first)
+            .position(self.log_table.selected_packet.saturating_sub(first));
+
         frame.render_stateful_widget(
             Scrollbar::new(ScrollbarOrientation::VerticalRight)
                 .begin_symbol(Some("↑"))
@@ -173,24 +205,4 @@ impl LogView {
             &mut self.scrollbar_state,
         );
     }
-
-    fn find_first_occurrence(&self, index: &usize) -> usize {
-        let filtered_logs = self.filtered_logs.read().unwrap();
-        let all_logs = self.log_table.logs.read().unwrap();
-
-        let current_date = all_logs[filtered_logs[*index]].timestamp.naive_local();
-
-        let mut new_index = *index;
-
-        while new_index > 0
-            && all_logs[filtered_logs[new_index]].timestamp.naive_local() >= current_date
-        {
-            new_index -= 1;
-        }
-
-        match new_index {
-            0 => 0,
-            _ => new_index + 1,
-        }
-    }
 }
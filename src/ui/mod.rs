@@ -1,3 +1,5 @@
+pub mod command_status;
 pub mod log_info;
 pub mod log_table;
 pub mod log_view;
+pub mod rejects;
@@ -0,0 +1,63 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Style, Stylize},
+    text::{Line, Text},
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+
+use crate::{ingesters::rejects::RejectedLogs, ui::log_info::center};
+
+//Lists the most recent lines that failed to parse.
+pub struct RejectsPopup<'a> {
+    pub border_style: Style,
+    pub rejects: &'a RejectedLogs,
+}
+
+impl<'a> RejectsPopup<'a> {
+    pub fn new(rejects: &'a RejectedLogs) -> Self {
+        Self {
+            border_style: Style::default().yellow(),
+            rejects,
+        }
+    }
+}
+
+impl Widget for RejectsPopup<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let area = center(area, Constraint::Percentage(80), Constraint::Percentage(60));
+        Clear.render(area, buf);
+
+        let block = Block::new()
+            .title(Line::from(format!(
+                " rejected lines: {} (syslog header: {}, filterlog: {}, timestamp: {}) ",
+                self.rejects.total(),
+                self.rejects.syslog_header,
+                self.rejects.filterlog,
+                self.rejects.timestamp
+            )))
+            .borders(Borders::ALL)
+            .border_style(self.border_style);
+
+        //Two lines per entry, keep the newest ones that fit.
+        let visible = (block.inner(area).height / 2) as usize;
+        let entries = self.rejects.entries();
+        let content: Vec<_> = entries
+            .iter()
+            .skip(entries.len().saturating_sub(visible))
+            .flat_map(|entry| {
+                [
+                    Line::from(vec![
+                        format!(" {} ", entry.received.format("%H:%M:%S")).bold(),
+                        entry.reason.to_string().red(),
+                    ]),
+                    Line::from(format!(" {}", entry.raw_log)).gray(),
+                ]
+            })
+            .collect();
+
+        Paragraph::new(Text::from(content))
+            .block(block)
+            .render(area, buf);
+    }
+}